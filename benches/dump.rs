@@ -0,0 +1,105 @@
+//! Measures the throughput of `hexx dump` and, if installed, of xxd on the same input.
+//!
+//! Run with `cargo bench --bench dump`. Set `HEXXER_BENCH_MIB` to change the input size.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 3;
+
+/// Fills `size` bytes with a mix of text, NUL runs and pseudo random data.
+fn generate_input(path: &Path, size: usize) {
+    let mut data = Vec::with_capacity(size);
+    let mut state: u32 = 0x2545_f491;
+
+    while data.len() < size {
+        match data.len() / 4096 % 3 {
+            0 => data.extend(
+                b"The quick brown fox jumps over the lazy dog.\n"
+                    .iter()
+                    .cycle()
+                    .take(4096),
+            ),
+            1 => data.extend(std::iter::repeat_n(0u8, 4096)),
+            _ => {
+                for _ in 0..4096 {
+                    // xorshift32
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    data.push(state as u8);
+                }
+            }
+        }
+    }
+    data.truncate(size);
+
+    fs::write(path, data).expect("Could not write benchmark input");
+}
+
+/// Runs the command `RUNS` times with output discarded and returns the fastest run.
+fn measure(program: &str, args: &[&str]) -> Option<Duration> {
+    let mut fastest: Option<Duration> = None;
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let status = Command::new(program)
+            .args(args)
+            .stdout(Stdio::null())
+            .status()
+            .ok()?;
+        let elapsed = start.elapsed();
+        if !status.success() {
+            return None;
+        }
+        fastest = Some(fastest.map_or(elapsed, |fastest| fastest.min(elapsed)));
+    }
+
+    fastest
+}
+
+fn report(name: &str, size: usize, duration: Option<Duration>) {
+    match duration {
+        Some(duration) => {
+            let throughput = size as f64 / (1024.0 * 1024.0) / duration.as_secs_f64();
+            println!(
+                "{name:<32} {:>8.3} s {throughput:>10.1} MiB/s",
+                duration.as_secs_f64()
+            );
+        }
+        None => println!("{name:<32} {:>10}", "n/a"),
+    }
+}
+
+fn main() {
+    let mib: usize = env::var("HEXXER_BENCH_MIB")
+        .ok()
+        .and_then(|mib| mib.parse().ok())
+        .unwrap_or(64);
+    let size = mib * 1024 * 1024;
+
+    let input = env::temp_dir().join(format!("hexxer-bench-{}.bin", std::process::id()));
+    generate_input(&input, size);
+    let input_arg = input.to_str().expect("temp dir is valid UTF-8");
+    let hexx = env!("CARGO_BIN_EXE_hexx");
+
+    println!("dumping {mib} MiB, best of {RUNS} runs");
+    report("hexx dump", size, measure(hexx, &["dump", input_arg]));
+    report(
+        "hexx dump --color always",
+        size,
+        measure(hexx, &["dump", "--color", "always", input_arg]),
+    );
+    report(
+        "hexx dump --plain",
+        size,
+        measure(hexx, &["dump", "--plain", input_arg]),
+    );
+    report("xxd", size, measure("xxd", &[input_arg]));
+    report("xxd -p", size, measure("xxd", &["-p", input_arg]));
+
+    let _ = fs::remove_file(&input);
+}
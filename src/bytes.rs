@@ -0,0 +1,9 @@
+/// The value of a word of up to 8 octets in the given byte order.
+pub fn word_value(bytes: &[u8], little_endian: bool) -> u64 {
+    let push = |value: u64, byte: &u8| value << 8 | u64::from(*byte);
+    if little_endian {
+        bytes.iter().rev().fold(0, push)
+    } else {
+        bytes.iter().fold(0, push)
+    }
+}
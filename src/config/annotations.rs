@@ -0,0 +1,200 @@
+use super::color_choice::Shade;
+use super::highlight::{Highlight, Target};
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// An offset or length, as a number or as a string like `"0x1c"`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Number {
+    Integer(usize),
+    Text(String),
+}
+
+impl Number {
+    fn value(&self) -> Result<usize, String> {
+        match self {
+            Number::Integer(value) => Ok(*value),
+            Number::Text(text) => {
+                let text = text.trim();
+                match text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+                    Some(hex) => usize::from_str_radix(hex, 16),
+                    None => text.parse(),
+                }
+                .map_err(|_| format!("invalid number '{text}'"))
+            }
+        }
+    }
+}
+
+/// A labeled region of the dump.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Region {
+    offset: Number,
+    length: Number,
+    label: String,
+    color: Option<String>,
+}
+
+/// Contents of an annotation file: `[[regions]]` tables in TOML, a `regions` array in JSON. A
+/// JSON file may also be just the array of regions.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnnotationFile {
+    #[serde(default)]
+    regions: Vec<Region>,
+}
+
+impl Region {
+    fn highlight(&self) -> Result<Highlight, String> {
+        let start = self.offset.value()?;
+        let end = start
+            .checked_add(self.length.value()?)
+            .ok_or_else(|| "the region is too large".to_string())?;
+        let color = match &self.color {
+            Some(color) => Some(
+                color
+                    .parse::<Shade>()?
+                    .color
+                    .ok_or_else(|| format!("'{color}' is not a color"))?,
+            ),
+            None => None,
+        };
+
+        Ok(Highlight {
+            target: Target::Region { start, end },
+            color,
+            label: self.label.clone(),
+        })
+    }
+}
+
+/// Loads the regions of an annotation file as highlights. Files ending in `.json` are read as
+/// JSON, all others as TOML.
+pub fn load(path: &Path) -> io::Result<Vec<Highlight>> {
+    let contents = fs::read_to_string(path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Could not read annotation file {}: {err}", path.display()),
+        )
+    })?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    parse(&contents, is_json).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid annotation file {}: {err}", path.display()),
+        )
+    })
+}
+
+/// Parses the regions of an annotation file as highlights.
+fn parse(contents: &str, is_json: bool) -> Result<Vec<Highlight>, String> {
+    let regions = if is_json {
+        let value: serde_json::Value =
+            serde_json::from_str(contents).map_err(|err| err.to_string())?;
+        if value.is_array() {
+            serde_json::from_value(value)
+        } else {
+            serde_json::from_value::<AnnotationFile>(value).map(|file| file.regions)
+        }
+        .map_err(|err| err.to_string())?
+    } else {
+        toml::from_str::<AnnotationFile>(contents)
+            .map_err(|err| err.to_string())?
+            .regions
+    };
+    regions
+        .iter()
+        .map(|region| {
+            region
+                .highlight()
+                .map_err(|err| format!("region '{}': {err}", region.label))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The start, end and label of every region.
+    fn regions(contents: &str, is_json: bool) -> Vec<(usize, usize, String)> {
+        parse(contents, is_json)
+            .expect("valid annotations")
+            .into_iter()
+            .map(|highlight| match highlight.target {
+                Target::Region { start, end } => (start, end, highlight.label),
+                target => panic!("{target:?} is not a region"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn toml_regions() {
+        let toml = r#"
+            [[regions]]
+            offset = 0
+            length = 4
+            label = "magic"
+            color = "red"
+
+            [[regions]]
+            offset = "0x1c"
+            length = "0X8"
+            label = "entry"
+        "#;
+        assert_eq!(
+            regions(toml, false),
+            [
+                (0, 4, "magic".to_string()),
+                (0x1c, 0x24, "entry".to_string())
+            ]
+        );
+        let highlights = parse(toml, false).expect("valid annotations");
+        assert!(highlights[0].color.is_some());
+        assert!(highlights[1].color.is_none());
+    }
+
+    #[test]
+    fn json_regions() {
+        let json = r#"{"regions": [{"offset": "0x10", "length": 2, "label": "flags"}]}"#;
+        assert_eq!(regions(json, true), [(0x10, 0x12, "flags".to_string())]);
+        // or just the array
+        let json = r#"[{"offset": 16, "length": " 0x2 ", "label": "flags"}]"#;
+        assert_eq!(regions(json, true), [(0x10, 0x12, "flags".to_string())]);
+        assert!(regions("{}", true).is_empty());
+    }
+
+    #[test]
+    fn invalid_regions() {
+        let error = |contents: &str, is_json: bool| {
+            parse(contents, is_json).expect_err("invalid annotations")
+        };
+        assert_eq!(
+            error(r#"[{"offset": "0xzz", "length": 1, "label": "a"}]"#, true),
+            "region 'a': invalid number '0xzz'"
+        );
+        assert!(
+            error(
+                r#"[{"offset": 1, "length": 1, "label": "a", "colour": "red"}]"#,
+                true
+            )
+            .contains("colour")
+        );
+        assert!(error("[[regions]]\noffset = 1\nlabel = \"a\"\n", false).contains("length"));
+        assert_eq!(
+            error(
+                r#"[{"offset": 1, "length": 1, "label": "a", "color": "bold"}]"#,
+                true
+            ),
+            "region 'a': 'bold' is not a color"
+        );
+    }
+}
@@ -1,22 +1,470 @@
 use clap::ValueEnum;
-use owo_colors::{CssColors, DynColors };
+use owo_colors::{AnsiColors, CssColors, DynColor, DynColors, OwoColorize, Style, XtermColors};
+use serde::Deserialize;
 
-#[derive(Debug, Copy, Clone, ValueEnum)]
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ColorChoice {
     Auto,
     Never,
     Always,
 }
 
+/// How the text of a byte class is drawn: optional foreground and background colors plus text
+/// effects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Shade {
+    pub color: Option<DynColors>,
+    pub background: Option<DynColors>,
+    pub bold: bool,
+    pub dim: bool,
+}
+
+impl Shade {
+    pub const fn color(color: DynColors) -> Self {
+        Self {
+            color: Some(color),
+            background: None,
+            bold: false,
+            dim: false,
+        }
+    }
+
+    pub const fn plain() -> Self {
+        Self {
+            color: None,
+            background: None,
+            bold: false,
+            dim: false,
+        }
+    }
+
+    pub const fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    pub const fn dim(self) -> Self {
+        Self { dim: true, ..self }
+    }
+
+    pub const fn on(self, background: DynColors) -> Self {
+        Self {
+            background: Some(background),
+            ..self
+        }
+    }
+
+    pub fn style(&self) -> Style {
+        let mut style = Style::new();
+        if let Some(color) = self.color {
+            style = style.color(color);
+        }
+        if let Some(background) = self.background {
+            style = style.on_color(background);
+        }
+        if self.bold {
+            style = style.bold();
+        }
+        if self.dim {
+            style = style.dimmed();
+        }
+        style
+    }
+}
+
+/// Parses a color given as `#rrggbb`, as an ANSI color name like `red` or `bright blue` or as an
+/// xterm 256 color index, optionally preceded by the effects `bold` and `dim`.
+impl FromStr for Shade {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut shade = Shade::plain();
+        let mut color = value.trim();
+        loop {
+            if let Some(rest) = color.strip_prefix("bold") {
+                shade = shade.bold();
+                color = rest.trim_start();
+            } else if let Some(rest) = color.strip_prefix("dim") {
+                shade = shade.dim();
+                color = rest.trim_start();
+            } else {
+                break;
+            }
+        }
+
+        if color.is_empty() {
+            if shade == Shade::plain() {
+                return Err(format!("invalid color '{value}'"));
+            }
+        } else if let Ok(index) = color.parse::<u8>() {
+            shade.color = Some(DynColors::Xterm(XtermColors::from(index)));
+        } else {
+            shade.color = Some(
+                color
+                    .parse::<DynColors>()
+                    .map_err(|_| format!("invalid color '{value}'"))?,
+            );
+        }
+
+        Ok(shade)
+    }
+}
+
+/// Colors the byte values `start..=end` and optionally shows them as `glyph` in the text column,
+/// overriding their byte class.
+#[derive(Debug, Copy, Clone)]
+pub struct ColorRule {
+    pub start: u8,
+    pub end: u8,
+    pub shade: Option<Shade>,
+    pub glyph: Option<char>,
+}
+
+impl ColorRule {
+    pub fn matches(&self, byte: u8) -> bool {
+        (self.start..=self.end).contains(&byte)
+    }
+
+    /// Parses a byte value like `0xff` or `255`, or a range of them like `0x80-0xbf`.
+    pub fn parse_bytes(bytes: &str) -> Result<(u8, u8), String> {
+        let value = |digits: &str| {
+            let digits = digits.trim();
+            match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+                Some(hex) => u8::from_str_radix(hex, 16),
+                None => digits.parse(),
+            }
+            .map_err(|_| format!("invalid byte value '{digits}'"))
+        };
+
+        let (start, end) = match bytes.split_once('-') {
+            Some((start, end)) => (value(start)?, value(end)?),
+            None => {
+                let byte = value(bytes)?;
+                (byte, byte)
+            }
+        };
+        if start > end {
+            return Err(format!("empty byte range '{bytes}'"));
+        }
+        Ok((start, end))
+    }
+}
+
+/// Parses `<bytes>=[<color>][:<glyph>]`, e.g. `0xff=red`, `0x80-0xbf=bold #ff8800:~` or
+/// `0x00=:_`.
+impl FromStr for ColorRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let Some((bytes, appearance)) = rule.split_once('=') else {
+            return Err(format!(
+                "invalid color rule '{rule}', expected <bytes>=[<color>][:<glyph>]"
+            ));
+        };
+        let (start, end) = Self::parse_bytes(bytes)?;
+        let (color, glyph) = match appearance.split_once(':') {
+            Some((color, glyph)) => (color, Some(glyph)),
+            None => (appearance, None),
+        };
+
+        let shade = match color.trim() {
+            "" => None,
+            color => Some(color.parse()?),
+        };
+        let glyph = match glyph {
+            Some(glyph) => {
+                let mut chars = glyph.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(glyph), None) => Some(glyph),
+                    _ => return Err(format!("the glyph '{glyph}' is not a single character")),
+                }
+            }
+            None => None,
+        };
+        if shade.is_none() && glyph.is_none() {
+            return Err(format!("the color rule '{rule}' sets neither a color nor a glyph"));
+        }
+
+        Ok(Self {
+            start,
+            end,
+            shade,
+            glyph,
+        })
+    }
+}
+
+/// The colors a terminal can show, from none to 24 bit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    None,
+    Ansi16,
+    Xterm256,
+    Truecolor,
+}
+
+/// The default palette of xterm for the 16 ANSI colors.
+const ANSI_PALETTE: [(AnsiColors, (u8, u8, u8)); 16] = [
+    (AnsiColors::Black, (0, 0, 0)),
+    (AnsiColors::Red, (205, 0, 0)),
+    (AnsiColors::Green, (0, 205, 0)),
+    (AnsiColors::Yellow, (205, 205, 0)),
+    (AnsiColors::Blue, (0, 0, 238)),
+    (AnsiColors::Magenta, (205, 0, 205)),
+    (AnsiColors::Cyan, (0, 205, 205)),
+    (AnsiColors::White, (229, 229, 229)),
+    (AnsiColors::BrightBlack, (127, 127, 127)),
+    (AnsiColors::BrightRed, (255, 0, 0)),
+    (AnsiColors::BrightGreen, (0, 255, 0)),
+    (AnsiColors::BrightYellow, (255, 255, 0)),
+    (AnsiColors::BrightBlue, (92, 92, 255)),
+    (AnsiColors::BrightMagenta, (255, 0, 255)),
+    (AnsiColors::BrightCyan, (0, 255, 255)),
+    (AnsiColors::BrightWhite, (255, 255, 255)),
+];
+
+/// The levels of each channel in the 6x6x6 color cube of the xterm 256 color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Detects the colors stdout can show. `NO_COLOR` disables and `CLICOLOR_FORCE` forces colors
+    /// unless `--color` is given; `COLORTERM` and `TERM` tell the depth.
+    pub fn detect(color_choice: ColorChoice) -> Self {
+        Self::detect_from(
+            color_choice,
+            |name| env::var(name).ok(),
+            io::stdout().is_terminal(),
+        )
+    }
+
+    /// Detects the depth from the environment variables `env` gives and whether the output is a
+    /// terminal.
+    fn detect_from(
+        color_choice: ColorChoice,
+        env: impl Fn(&str) -> Option<String>,
+        is_terminal: bool,
+    ) -> Self {
+        let var = |name: &str| env(name).filter(|value| !value.is_empty());
+
+        let forced = match color_choice {
+            ColorChoice::Never => return ColorDepth::None,
+            ColorChoice::Always => true,
+            ColorChoice::Auto => {
+                if var("NO_COLOR").is_some() {
+                    return ColorDepth::None;
+                }
+                var("CLICOLOR_FORCE").is_some_and(|value| value != "0")
+            }
+        };
+        if !forced && !is_terminal {
+            return ColorDepth::None;
+        }
+
+        let term = var("TERM").unwrap_or_default();
+        if term == "dumb" {
+            // a dumb terminal shows escapes as garbage, only the basic colors if forced
+            return if forced {
+                ColorDepth::Ansi16
+            } else {
+                ColorDepth::None
+            };
+        }
+        if matches!(var("COLORTERM").as_deref(), Some("truecolor" | "24bit"))
+            || term.ends_with("-direct")
+        {
+            ColorDepth::Truecolor
+        } else if term.contains("256color") {
+            ColorDepth::Xterm256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// Maps `color` to the nearest color this depth can show.
+    pub fn downgrade(self, color: DynColors) -> DynColors {
+        let Some(rgb) = rgb(color) else {
+            return color; // the ANSI colors exist everywhere
+        };
+
+        match self {
+            ColorDepth::None | ColorDepth::Truecolor => color,
+            ColorDepth::Xterm256 => match color {
+                DynColors::Xterm(_) => color,
+                _ => DynColors::Xterm(XtermColors::from(nearest_xterm(rgb))),
+            },
+            ColorDepth::Ansi16 => DynColors::Ansi(nearest_ansi(rgb)),
+        }
+    }
+}
+
+/// Writes the raw SGR parameters of a foreground color, e.g. `38;2;95;158;160`.
+struct RawForeground(DynColors);
+
+impl fmt::Display for RawForeground {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_raw_ansi_fg(f)
+    }
+}
+
+/// The red, green and blue channels of a color, `None` for the ANSI colors whose look is up to
+/// the terminal.
+fn rgb(color: DynColors) -> Option<(u8, u8, u8)> {
+    match color {
+        DynColors::Ansi(_) => None,
+        DynColors::Rgb(r, g, b) => Some((r, g, b)),
+        DynColors::Xterm(xterm) => {
+            let index = u8::from(xterm);
+            Some(match index {
+                0..=15 => ANSI_PALETTE[index as usize].1,
+                16..=231 => {
+                    let cube = index - 16;
+                    (
+                        CUBE_LEVELS[(cube / 36) as usize],
+                        CUBE_LEVELS[(cube / 6 % 6) as usize],
+                        CUBE_LEVELS[(cube % 6) as usize],
+                    )
+                }
+                _ => {
+                    let gray = 8 + (index - 232) * 10;
+                    (gray, gray, gray)
+                }
+            })
+        }
+        DynColors::Css(_) => {
+            // owo-colors keeps the channels of the CSS colors to itself, but writes them
+            let raw = RawForeground(color).to_string();
+            let channels: Vec<u8> = raw
+                .strip_prefix("38;2;")?
+                .split(';')
+                .filter_map(|channel| channel.parse().ok())
+                .collect();
+            match channels[..] {
+                [r, g, b] => Some((r, g, b)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Squared distance of two colors, weighted by how sensitive the eye is to each channel.
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |x: u8, y: u8| (i32::from(x) - i32::from(y)).unsigned_abs().pow(2);
+    2 * channel(a.0, b.0) + 4 * channel(a.1, b.1) + 3 * channel(a.2, b.2)
+}
+
+/// The ANSI color of the same hue and brightness. The distance to the 16 colors would turn most
+/// pastel shades gray, as the basic colors are fully saturated.
+fn nearest_ansi((r, g, b): (u8, u8, u8)) -> AnsiColors {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+
+    if max - min < 48 {
+        let average = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+        return match average {
+            0..64 => AnsiColors::Black,
+            64..150 => AnsiColors::BrightBlack,
+            150..220 => AnsiColors::White,
+            _ => AnsiColors::BrightWhite,
+        };
+    }
+
+    // the channels above the middle make the hue
+    let middle = (u16::from(max) + u16::from(min)) / 2;
+    let on = |channel: u8| u16::from(channel) > middle;
+    let bright = middle > 150;
+    match (on(r), on(g), on(b), bright) {
+        (true, false, false, false) => AnsiColors::Red,
+        (false, true, false, false) => AnsiColors::Green,
+        (true, true, false, false) => AnsiColors::Yellow,
+        (false, false, true, false) => AnsiColors::Blue,
+        (true, false, true, false) => AnsiColors::Magenta,
+        (false, true, true, false) => AnsiColors::Cyan,
+        (true, false, false, true) => AnsiColors::BrightRed,
+        (false, true, false, true) => AnsiColors::BrightGreen,
+        (true, true, false, true) => AnsiColors::BrightYellow,
+        (false, false, true, true) => AnsiColors::BrightBlue,
+        (true, false, true, true) => AnsiColors::BrightMagenta,
+        (false, true, true, true) => AnsiColors::BrightCyan,
+        (_, _, _, false) => AnsiColors::White,
+        (_, _, _, true) => AnsiColors::BrightWhite,
+    }
+}
+
+/// The index of the nearest color in the cube or on the gray ramp of the xterm 256 color palette.
+fn nearest_xterm(rgb: (u8, u8, u8)) -> u8 {
+    let level = |channel: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(channel))
+            .expect("levels are not empty") as u8
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (
+        16 + 36 * r + 6 * g + b,
+        (CUBE_LEVELS[r as usize], CUBE_LEVELS[g as usize], CUBE_LEVELS[b as usize]),
+    );
+
+    let average = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + step * 10;
+    let gray = (232 + step, (gray_level, gray_level, gray_level));
+
+    [cube, gray]
+        .into_iter()
+        .min_by_key(|(_, candidate)| distance(rgb, *candidate))
+        .expect("candidates are not empty")
+        .0
+}
+
+pub fn colorize(text: &str, shade: Shade, color_depth: ColorDepth) -> String {
+    if color_depth == ColorDepth::None {
+        return text.to_string();
+    }
+    let shade = Shade {
+        color: shade.color.map(|color| color_depth.downgrade(color)),
+        background: shade.background.map(|color| color_depth.downgrade(color)),
+        ..shade
+    };
+    text.style(shade.style()).to_string()
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct LineColors {
-    normal: DynColors,
-    alternate: DynColors,
+    normal: Shade,
+    alternate: Shade,
 }
 
 impl LineColors {
+    pub const fn new(normal: Shade, alternate: Shade) -> Self {
+        Self { normal, alternate }
+    }
+
+    const fn css(normal: CssColors, alternate: CssColors) -> Self {
+        Self::new(
+            Shade::color(DynColors::Css(normal)),
+            Shade::color(DynColors::Css(alternate)),
+        )
+    }
+
+    const fn rgb(normal: (u8, u8, u8), alternate: (u8, u8, u8)) -> Self {
+        Self::new(
+            Shade::color(DynColors::Rgb(normal.0, normal.1, normal.2)),
+            Shade::color(DynColors::Rgb(alternate.0, alternate.1, alternate.2)),
+        )
+    }
+
+    const fn ansi(normal: AnsiColors, alternate: AnsiColors) -> Self {
+        Self::new(
+            Shade::color(DynColors::Ansi(normal)).bold(),
+            Shade::color(DynColors::Ansi(alternate)).bold(),
+        )
+    }
 
-    pub fn get(&self, is_alternate: bool) -> DynColors
+    pub fn get(&self, is_alternate: bool) -> Shade
     {
         if is_alternate {
             self.alternate
@@ -37,32 +485,293 @@ pub struct LineColorConfig {
 
 impl Default for LineColorConfig {
     fn default() -> Self {
-        Self {
-            panel_text: LineColors {
-                normal: DynColors::Css(CssColors::LightBlue),
-                alternate: DynColors::Css(CssColors::CadetBlue),
-            },
-            dump_text: LineColors {
-                normal: DynColors::Css(CssColors::LightSteelBlue),
-                alternate: DynColors::Css(CssColors::LightSlateGray),
+        Theme::Dark.colors()
+    }
+}
+
+/// Built-in color themes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Truecolor shades for dark backgrounds
+    Dark,
+    /// Truecolor shades for light backgrounds
+    Light,
+    /// Bold basic colors that also work on 16 color terminals
+    HighContrast,
+    /// The Okabe-Ito palette, distinguishable with all common color vision deficiencies
+    Colorblind,
+    /// No colors, only bold and dim text
+    Monochrome,
+}
+
+impl Theme {
+    pub fn colors(&self) -> LineColorConfig {
+        match self {
+            Theme::Dark => LineColorConfig {
+                panel_text: LineColors::css(CssColors::LightBlue, CssColors::CadetBlue),
+                dump_text: LineColors::css(CssColors::LightSteelBlue, CssColors::LightSlateGray),
+                nul_char: LineColors::css(CssColors::DimGray, CssColors::DarkGray),
+                control_char: LineColors::css(CssColors::LawnGreen, CssColors::GreenYellow),
+                undefined_char: LineColors::css(CssColors::LightCoral, CssColors::IndianRed),
             },
-            nul_char: LineColors {
-                normal: DynColors::Css(CssColors::DimGray),
-                alternate: DynColors::Css(CssColors::DarkGray),
+            Theme::Light => LineColorConfig {
+                panel_text: LineColors::css(CssColors::SteelBlue, CssColors::DarkSlateBlue),
+                dump_text: LineColors::css(CssColors::Black, CssColors::DarkSlateGray),
+                nul_char: LineColors::css(CssColors::DarkGray, CssColors::Gray),
+                control_char: LineColors::css(CssColors::ForestGreen, CssColors::DarkGreen),
+                undefined_char: LineColors::css(CssColors::FireBrick, CssColors::DarkRed),
             },
-            control_char: LineColors {
-                normal: DynColors::Css(CssColors::LawnGreen),
-                alternate: DynColors::Css(CssColors::GreenYellow),
+            Theme::HighContrast => LineColorConfig {
+                panel_text: LineColors::ansi(AnsiColors::BrightWhite, AnsiColors::BrightCyan),
+                dump_text: LineColors::ansi(AnsiColors::BrightWhite, AnsiColors::BrightYellow),
+                nul_char: LineColors::ansi(AnsiColors::BrightBlack, AnsiColors::White),
+                control_char: LineColors::ansi(AnsiColors::BrightGreen, AnsiColors::Green),
+                undefined_char: LineColors::ansi(AnsiColors::BrightRed, AnsiColors::BrightMagenta),
             },
-            undefined_char: LineColors {
-                normal: DynColors::Css(CssColors::LightCoral),
-                alternate: DynColors::Css(CssColors::IndianRed),
+            Theme::Colorblind => LineColorConfig {
+                panel_text: LineColors::rgb((0x56, 0xb4, 0xe9), (0x00, 0x72, 0xb2)),
+                dump_text: LineColors::rgb((0xdd, 0xdd, 0xdd), (0xbb, 0xbb, 0xbb)),
+                nul_char: LineColors::rgb((0x88, 0x88, 0x88), (0x66, 0x66, 0x66)),
+                control_char: LineColors::rgb((0xf0, 0xe4, 0x42), (0xe6, 0x9f, 0x00)),
+                undefined_char: LineColors::rgb((0xd5, 0x5e, 0x00), (0xcc, 0x79, 0xa7)),
             },
+            Theme::Monochrome => {
+                let same = |shade: Shade| LineColors::new(shade, shade);
+                LineColorConfig {
+                    panel_text: same(Shade::plain().dim()),
+                    dump_text: same(Shade::plain()),
+                    nul_char: same(Shade::plain().dim()),
+                    control_char: same(Shade::plain().bold()),
+                    undefined_char: same(Shade::plain().bold()),
+                }
+            }
+        }
+    }
+}
+
+/// Prints a sample of every byte class in both shades for each theme.
+pub fn print_color_table<W: Write>(out: &mut W, color_depth: ColorDepth) -> io::Result<()> {
+    for theme in Theme::value_variants() {
+        let colors = theme.colors();
+        let name = theme
+            .to_possible_value()
+            .expect("no values are skipped")
+            .get_name()
+            .to_string();
+        writeln!(out, "{name}")?;
+
+        let classes = [
+            ("panel text", colors.panel_text, "00000010:"),
+            ("dump text", colors.dump_text, "41 42 43"),
+            ("nul char", colors.nul_char, "00 \u{2400}"),
+            ("control char", colors.control_char, "0a \u{240a}"),
+            ("undefined char", colors.undefined_char, "ff ."),
+        ];
+        for (class, line_colors, sample) in classes {
+            writeln!(
+                out,
+                "  {class:<16}{}  {}",
+                colorize(&format!("{sample:<10}"), line_colors.get(false), color_depth),
+                colorize(&format!("{sample:<10}"), line_colors.get(true), color_depth)
+            )?;
         }
     }
+
+    Ok(())
 }
-impl LineColorConfig {
-    pub fn new() -> Self {
-        Self::default()
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(colors: &LineColorConfig) -> [LineColors; 5] {
+        [
+            colors.panel_text,
+            colors.dump_text,
+            colors.nul_char,
+            colors.control_char,
+            colors.undefined_char,
+        ]
+    }
+
+    #[test]
+    fn colored_themes_alternate_the_shade_of_every_class() {
+        for theme in [
+            Theme::Dark,
+            Theme::Light,
+            Theme::HighContrast,
+            Theme::Colorblind,
+        ] {
+            for class in classes(&theme.colors()) {
+                let (normal, alternate) = (class.get(false), class.get(true));
+                assert!(
+                    normal.color.is_some() && alternate.color.is_some(),
+                    "{theme:?}"
+                );
+                assert_ne!(normal, alternate, "{theme:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn high_contrast_only_uses_the_ansi_colors() {
+        for class in classes(&Theme::HighContrast.colors()) {
+            for shade in [class.get(false), class.get(true)] {
+                assert!(matches!(shade.color, Some(DynColors::Ansi(_))));
+                assert!(shade.bold);
+            }
+        }
+    }
+
+    #[test]
+    fn monochrome_has_no_colors() {
+        for class in classes(&Theme::Monochrome.colors()) {
+            assert_eq!(class.get(false), class.get(true));
+            assert_eq!(class.get(false).color, None);
+            assert_eq!(class.get(false).background, None);
+        }
+    }
+
+    #[test]
+    fn dark_is_the_default_theme() {
+        let default = LineColorConfig::default();
+        let dark = Theme::Dark.colors();
+        for (default, dark) in classes(&default).iter().zip(classes(&dark)) {
+            assert_eq!(default.get(false), dark.get(false));
+            assert_eq!(default.get(true), dark.get(true));
+        }
+    }
+
+    #[test]
+    fn color_table_lists_every_theme() {
+        let mut table = Vec::new();
+        print_color_table(&mut table, ColorDepth::None).expect("writes to a vector");
+        let table = String::from_utf8(table).expect("table is text");
+
+        let themes: Vec<&str> = table
+            .lines()
+            .filter(|line| !line.starts_with(' '))
+            .collect();
+        assert_eq!(
+            themes,
+            ["dark", "light", "high-contrast", "colorblind", "monochrome"]
+        );
+        assert_eq!(table.lines().count(), 5 * 6);
+        assert!(!table.contains('\x1b'));
+    }
+
+    /// `ColorDepth::detect_from` with only the given environment variables set.
+    fn detect(color_choice: ColorChoice, vars: &[(&str, &str)], is_terminal: bool) -> ColorDepth {
+        let env = |name: &str| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        };
+        ColorDepth::detect_from(color_choice, env, is_terminal)
+    }
+
+    #[test]
+    fn no_color_wins_over_clicolor_force() {
+        let vars = [
+            ("NO_COLOR", "1"),
+            ("CLICOLOR_FORCE", "1"),
+            ("TERM", "xterm"),
+        ];
+        assert_eq!(detect(ColorChoice::Auto, &vars, true), ColorDepth::None);
+        // an empty variable is not set
+        let vars = [("NO_COLOR", ""), ("TERM", "xterm")];
+        assert_eq!(detect(ColorChoice::Auto, &vars, true), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn color_flag_wins_over_the_environment() {
+        let vars = [("NO_COLOR", "1"), ("TERM", "xterm")];
+        assert_eq!(
+            detect(ColorChoice::Always, &vars, false),
+            ColorDepth::Ansi16
+        );
+        let vars = [("CLICOLOR_FORCE", "1"), ("TERM", "xterm")];
+        assert_eq!(detect(ColorChoice::Never, &vars, true), ColorDepth::None);
+    }
+
+    #[test]
+    fn clicolor_force_colors_pipes() {
+        let vars = [("TERM", "xterm-256color")];
+        assert_eq!(detect(ColorChoice::Auto, &vars, false), ColorDepth::None);
+        let vars = [("CLICOLOR_FORCE", "1"), ("TERM", "xterm-256color")];
+        assert_eq!(
+            detect(ColorChoice::Auto, &vars, false),
+            ColorDepth::Xterm256
+        );
+        let vars = [("CLICOLOR_FORCE", "0"), ("TERM", "xterm-256color")];
+        assert_eq!(detect(ColorChoice::Auto, &vars, false), ColorDepth::None);
+    }
+
+    #[test]
+    fn colorterm_wins_over_term() {
+        let depth = |vars: &[(&str, &str)]| detect(ColorChoice::Auto, vars, true);
+        assert_eq!(
+            depth(&[("COLORTERM", "truecolor"), ("TERM", "xterm-256color")]),
+            ColorDepth::Truecolor
+        );
+        assert_eq!(depth(&[("COLORTERM", "24bit")]), ColorDepth::Truecolor);
+        assert_eq!(depth(&[("TERM", "xterm-direct")]), ColorDepth::Truecolor);
+        assert_eq!(depth(&[("TERM", "xterm-256color")]), ColorDepth::Xterm256);
+        assert_eq!(depth(&[("TERM", "xterm")]), ColorDepth::Ansi16);
+        assert_eq!(depth(&[]), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn dumb_terminal_only_gets_forced_colors() {
+        let vars = [("TERM", "dumb"), ("COLORTERM", "truecolor")];
+        assert_eq!(detect(ColorChoice::Auto, &vars, true), ColorDepth::None);
+        assert_eq!(detect(ColorChoice::Always, &vars, true), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn nearest_xterm_colors() {
+        assert_eq!(nearest_xterm((0, 0, 0)), 16);
+        assert_eq!(nearest_xterm((255, 255, 255)), 231);
+        assert_eq!(nearest_xterm((255, 0, 0)), 196);
+        assert_eq!(nearest_xterm((95, 135, 175)), 67);
+        // the gray ramp is finer than the gray of the cube
+        assert_eq!(nearest_xterm((128, 128, 128)), 244);
+        assert_eq!(
+            rgb(DynColors::Xterm(XtermColors::from(244))),
+            Some((128, 128, 128))
+        );
+    }
+
+    #[test]
+    fn nearest_ansi_colors() {
+        assert_eq!(nearest_ansi((10, 10, 10)), AnsiColors::Black);
+        assert_eq!(nearest_ansi((128, 128, 128)), AnsiColors::BrightBlack);
+        assert_eq!(nearest_ansi((255, 255, 255)), AnsiColors::BrightWhite);
+        assert_eq!(nearest_ansi((205, 0, 0)), AnsiColors::Red);
+        assert_eq!(nearest_ansi((0, 0, 205)), AnsiColors::Blue);
+        assert_eq!(nearest_ansi((255, 128, 128)), AnsiColors::BrightRed);
+        // pastel shades keep their hue
+        assert_eq!(nearest_ansi((173, 216, 230)), AnsiColors::BrightCyan);
+    }
+
+    #[test]
+    fn downgrade_to_the_depth() {
+        let cadet_blue = DynColors::Css(CssColors::CadetBlue);
+        assert_eq!(rgb(cadet_blue), Some((95, 158, 160)));
+        assert_eq!(ColorDepth::Truecolor.downgrade(cadet_blue), cadet_blue);
+        assert_eq!(
+            ColorDepth::Xterm256.downgrade(cadet_blue),
+            DynColors::Xterm(XtermColors::from(nearest_xterm((95, 158, 160))))
+        );
+        assert_eq!(
+            ColorDepth::Ansi16.downgrade(cadet_blue),
+            DynColors::Ansi(AnsiColors::Cyan)
+        );
+
+        let red = DynColors::Ansi(AnsiColors::Red);
+        assert_eq!(ColorDepth::Ansi16.downgrade(red), red);
+        let xterm = DynColors::Xterm(XtermColors::from(67));
+        assert_eq!(ColorDepth::Xterm256.downgrade(xterm), xterm);
     }
 }
@@ -0,0 +1,195 @@
+use super::color_choice::{ColorChoice, ColorRule, LineColorConfig, LineColors, Shade, Theme};
+use super::{Format, Language};
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// Environment variable that overrides the location of the configuration file.
+pub const CONFIG_ENV: &str = "HEXXER_CONFIG";
+
+/// Defaults for the dump command. Options given on the command line take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DumpDefaults {
+    pub cols: Option<u16>,
+    /// Octets per line of `--plain` dumps, which are much longer.
+    pub plain_cols: Option<u16>,
+    pub grouping: Option<u16>,
+    pub format: Option<Format>,
+    pub color: Option<ColorChoice>,
+    pub theme: Option<Theme>,
+    pub uppercase: Option<bool>,
+}
+
+/// Defaults for the generate command. Options given on the command line take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct GenerateDefaults {
+    pub cols: Option<u16>,
+    pub language: Option<Language>,
+    pub uppercase: Option<bool>,
+}
+
+/// Colors of one byte class. Missing shades keep their default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LineColorsFile {
+    pub normal: Option<String>,
+    pub alternate: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LineColorConfigFile {
+    pub panel_text: LineColorsFile,
+    pub dump_text: LineColorsFile,
+    pub nul_char: LineColorsFile,
+    pub control_char: LineColorsFile,
+    pub undefined_char: LineColorsFile,
+}
+
+/// A `[[color-rules]]` entry. See `ColorRule`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorRuleFile {
+    pub bytes: String,
+    pub color: Option<String>,
+    pub glyph: Option<char>,
+}
+
+/// Contents of the TOML configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub dump: DumpDefaults,
+    pub generate: GenerateDefaults,
+    pub colors: LineColorConfigFile,
+    pub color_rules: Vec<ColorRuleFile>,
+}
+
+fn parse_shade(value: &str, key: &str) -> io::Result<Shade> {
+    value.parse().map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid color for {key} in the configuration file: {err}"),
+        )
+    })
+}
+
+fn apply_line_colors(colors: &mut LineColors, file: &LineColorsFile, key: &str) -> io::Result<()> {
+    let normal = match &file.normal {
+        Some(value) => parse_shade(value, &format!("colors.{key}.normal"))?,
+        None => colors.get(false),
+    };
+    let alternate = match &file.alternate {
+        Some(value) => parse_shade(value, &format!("colors.{key}.alternate"))?,
+        None => colors.get(true),
+    };
+    *colors = LineColors::new(normal, alternate);
+    Ok(())
+}
+
+impl ConfigFile {
+    /// Overrides the colors set in the configuration file.
+    pub fn apply_colors(&self, colors: &mut LineColorConfig) -> io::Result<()> {
+        apply_line_colors(
+            &mut colors.panel_text,
+            &self.colors.panel_text,
+            "panel-text",
+        )?;
+        apply_line_colors(&mut colors.dump_text, &self.colors.dump_text, "dump-text")?;
+        apply_line_colors(&mut colors.nul_char, &self.colors.nul_char, "nul-char")?;
+        apply_line_colors(
+            &mut colors.control_char,
+            &self.colors.control_char,
+            "control-char",
+        )?;
+        apply_line_colors(
+            &mut colors.undefined_char,
+            &self.colors.undefined_char,
+            "undefined-char",
+        )
+    }
+
+    /// The color rules of the configuration file, in the order they are given.
+    pub fn color_rules(&self) -> io::Result<Vec<ColorRule>> {
+        self.color_rules
+            .iter()
+            .map(|rule| {
+                let invalid = |err: String| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Invalid color rule for {} in the configuration file: {err}",
+                            rule.bytes
+                        ),
+                    )
+                };
+                let (start, end) = ColorRule::parse_bytes(&rule.bytes).map_err(invalid)?;
+                let shade = match &rule.color {
+                    Some(color) => Some(color.parse().map_err(invalid)?),
+                    None => None,
+                };
+                Ok(ColorRule {
+                    start,
+                    end,
+                    shade,
+                    glyph: rule.glyph,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The default location: `$XDG_CONFIG_HOME/hexxer/config.toml` or
+/// `$HOME/.config/hexxer/config.toml`.
+fn default_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("hexxer").join("config.toml"))
+}
+
+/// Loads the configuration file given by `--config`, by the `HEXXER_CONFIG` environment variable
+/// or from the default location, in this order. Only a missing file at the default location is
+/// not an error.
+pub fn load(explicit: Option<&Path>) -> io::Result<ConfigFile> {
+    let (path, required) = match explicit
+        .map(PathBuf::from)
+        .or_else(|| env::var_os(CONFIG_ENV).map(PathBuf::from))
+    {
+        Some(path) => (path, true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(ConfigFile::default()),
+        },
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if !required && err.kind() == io::ErrorKind::NotFound => {
+            return Ok(ConfigFile::default());
+        }
+        Err(err) => {
+            return Err(io::Error::new(
+                err.kind(),
+                format!(
+                    "Could not read configuration file {}: {err}",
+                    path.display()
+                ),
+            ));
+        }
+    };
+
+    toml::from_str(&contents).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid configuration file {}: {err}", path.display()),
+        )
+    })
+}
@@ -0,0 +1,183 @@
+use super::color_choice::Shade;
+
+use owo_colors::DynColors;
+
+/// Background colors for highlights given without a color, used in turn. Dark enough to keep
+/// the text of every theme readable.
+const PALETTE: [DynColors; 6] = [
+    DynColors::Rgb(0x80, 0x20, 0x20),
+    DynColors::Rgb(0x20, 0x60, 0x20),
+    DynColors::Rgb(0x20, 0x40, 0x90),
+    DynColors::Rgb(0x70, 0x50, 0x00),
+    DynColors::Rgb(0x60, 0x20, 0x70),
+    DynColors::Rgb(0x10, 0x60, 0x60),
+];
+
+/// What a highlight marks.
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// The bytes at the offsets `start..end`, as shown in the dump.
+    Range { start: usize, end: usize },
+    /// Every occurrence of the bytes.
+    Pattern(Vec<u8>),
+    /// A labeled region of an annotation file, its label is shown next to the line it starts on.
+    Region { start: usize, end: usize },
+}
+
+/// Bytes shown with a background color, set with `--highlight`, `--highlight-pattern` or
+/// `--annotations`.
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    pub target: Target,
+    pub color: Option<DynColors>,
+    /// The highlight as given for the legend, or the label of a region.
+    pub label: String,
+}
+
+/// Parses an offset like `0x10` or `16`.
+fn parse_offset(offset: &str) -> Result<usize, String> {
+    let offset = offset.trim();
+    match offset.strip_prefix("0x").or(offset.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => offset.parse(),
+    }
+    .map_err(|_| format!("invalid offset '{offset}'"))
+}
+
+impl Highlight {
+    /// Parses `<offset>[..<end>|+<len>][:<color>]`, e.g. `0x10`, `0x10..0x18:red` or `16+8`.
+    /// The end is exclusive, a single offset highlights one byte.
+    pub fn range(spec: &str) -> Result<Self, String> {
+        let (range, color) = match spec.split_once(':') {
+            Some((range, color)) => (range, Some(color)),
+            None => (spec, None),
+        };
+
+        let (start, end) = if let Some((start, end)) = range.split_once("..") {
+            (parse_offset(start)?, parse_offset(end)?)
+        } else if let Some((start, len)) = range.split_once('+') {
+            let start = parse_offset(start)?;
+            let end = start
+                .checked_add(parse_offset(len)?)
+                .ok_or_else(|| format!("the range '{range}' is too large"))?;
+            (start, end)
+        } else {
+            let start = parse_offset(range)?;
+            (start, start.saturating_add(1))
+        };
+        if start >= end {
+            return Err(format!("empty range '{range}'"));
+        }
+
+        let color = match color {
+            Some(color) => Some(
+                color
+                    .parse::<Shade>()?
+                    .color
+                    .ok_or_else(|| format!("'{color}' is not a color"))?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            target: Target::Range { start, end },
+            color,
+            label: range.trim().to_string(),
+        })
+    }
+
+    /// Parses a pattern: hex digits after `0x`, like `0xdeadbeef` or `0x"de ad be ef"`, or
+    /// otherwise a string matched as its UTF-8 bytes.
+    pub fn pattern(spec: &str) -> Result<Self, String> {
+        let bytes = match spec.strip_prefix("0x").or(spec.strip_prefix("0X")) {
+            Some(hex) => {
+                let digits: Vec<char> = hex
+                    .chars()
+                    .filter(|ch| !ch.is_whitespace() && *ch != '"' && *ch != '_')
+                    .collect();
+                if !digits.len().is_multiple_of(2) {
+                    return Err(format!("odd number of hex digits in '{spec}'"));
+                }
+                digits
+                    .chunks(2)
+                    .map(|pair| {
+                        let pair: String = pair.iter().collect();
+                        u8::from_str_radix(&pair, 16).map_err(|_| format!("invalid octet '{pair}'"))
+                    })
+                    .collect::<Result<Vec<u8>, String>>()?
+            }
+            None => spec.as_bytes().to_vec(),
+        };
+        if bytes.is_empty() {
+            return Err("empty pattern".to_string());
+        }
+
+        Ok(Self {
+            target: Target::Pattern(bytes),
+            color: None,
+            label: spec.to_string(),
+        })
+    }
+
+    /// The background color of the `index`th highlight.
+    pub fn background(&self, index: usize) -> DynColors {
+        self.color.unwrap_or(PALETTE[index % PALETTE.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(spec: &str) -> (usize, usize) {
+        match Highlight::range(spec).expect("valid range").target {
+            Target::Range { start, end } => (start, end),
+            target => panic!("{target:?} is not a range"),
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(range("0x10..0x18"), (0x10, 0x18));
+        assert_eq!(range("16+8"), (16, 24));
+        assert_eq!(range("0X10"), (16, 17));
+
+        let highlight = Highlight::range("0x10..0x18:red").expect("valid range");
+        assert!(highlight.color.is_some());
+        assert_eq!(highlight.label, "0x10..0x18");
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        assert!(Highlight::range("0x18..0x10").is_err());
+        assert!(Highlight::range("16+0").is_err());
+        assert!(Highlight::range("0x").is_err());
+        assert!(Highlight::range("0..4:bold").is_err());
+        assert!(Highlight::range(&format!("{}+2", usize::MAX)).is_err());
+    }
+
+    #[test]
+    fn patterns() {
+        let bytes = |spec: &str| match Highlight::pattern(spec).expect("valid pattern").target {
+            Target::Pattern(bytes) => bytes,
+            target => panic!("{target:?} is not a pattern"),
+        };
+        assert_eq!(bytes("0xdeadbeef"), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(bytes("0x\"de ad_be ef\""), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(bytes("ELF"), b"ELF");
+
+        assert!(Highlight::pattern("0xabc").is_err());
+        assert!(Highlight::pattern("0xzz").is_err());
+        assert!(Highlight::pattern("").is_err());
+    }
+
+    #[test]
+    fn highlights_without_a_color_take_turns() {
+        let highlight = Highlight::pattern("ab").expect("valid pattern");
+        assert_eq!(highlight.background(1), PALETTE[1]);
+        assert_eq!(highlight.background(PALETTE.len()), PALETTE[0]);
+
+        let highlight = Highlight::range("0:#102030").expect("valid range");
+        assert_eq!(highlight.background(1), DynColors::Rgb(0x10, 0x20, 0x30));
+    }
+}
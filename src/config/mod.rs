@@ -1,19 +1,35 @@
+pub mod annotations;
 pub mod color_choice;
-use color_choice::{ColorChoice, LineColorConfig};
+pub mod file;
+pub mod highlight;
+pub mod value_type;
+use color_choice::{ColorChoice, ColorDepth, ColorRule, LineColorConfig, Theme};
+use file::ConfigFile;
+use highlight::Highlight;
+use value_type::ValueType;
 
+use std::fs;
 use std::path::PathBuf;
 use std::process;
+use std::str::FromStr;
 use std::io::{self, Write};
 
 use clap::builder::styling;
+use clap::parser::ValueSource;
 use clap::{Arg, ArgMatches, Command, ValueEnum, arg, value_parser};
+use serde::Deserialize;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Language {
     C,
     Cpp,
     Rust,
     Python,
+    Go,
+    Zig,
+    Nim,
+    D,
 }
 
 impl std::fmt::Display for Language {
@@ -25,7 +41,41 @@ impl std::fmt::Display for Language {
     }
 }
 
-#[derive(Debug, Copy, Clone, ValueEnum)]
+/// Type of the elements of a generated C or C++ array.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ElementType {
+    #[value(name = "uint8_t")]
+    Uint8T,
+    #[value(name = "unsigned-char", alias = "unsigned char")]
+    UnsignedChar,
+    Char,
+    /// C++17 only
+    #[value(name = "std::byte")]
+    StdByte,
+}
+
+/// What `generate` does with the octets of a trailing partial word.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Padding {
+    /// Fill the word up with 0x00
+    Zeros,
+    /// Fill the word up with 0xff, like erased flash memory
+    Ones,
+    /// Fail if the input is no whole number of words
+    Error,
+}
+
+/// How `generate --align` aligns a C or C++ array.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum AlignStyle {
+    /// _Alignas in C, alignas in C++
+    Standard,
+    /// __attribute__((aligned)) of GCC and Clang
+    Gnu,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Format {
     Hexadecimal,
     Octal,
@@ -34,59 +84,202 @@ pub enum Format {
 }
 
 impl Format {
-    pub fn value(&self, val: u8) -> String {
+    pub fn value(&self, val: u8, uppercase: bool) -> String {
         match self {
+            Format::Hexadecimal if uppercase => format!("{val:02X}"),
             Format::Hexadecimal => format!("{val:02x}"),
             Format::Octal => format!("{val:03o}"),
             Format::Decimal => format!("{val:03}"),
             Format::Binary => format!("{val:08b}"),
         }
     }
+
+    /// Number of digits `value` produces for a single octet.
+    pub fn width(&self) -> usize {
+        match self {
+            Format::Hexadecimal => 2,
+            Format::Octal | Format::Decimal => 3,
+            Format::Binary => 8,
+        }
+    }
+
+    pub fn radix(&self) -> u32 {
+        match self {
+            Format::Hexadecimal => 16,
+            Format::Octal => 8,
+            Format::Decimal => 10,
+            Format::Binary => 2,
+        }
+    }
+
+    /// Parses the digits of a single octet as `value` produces them.
+    pub fn parse(&self, digits: &str) -> Option<u8> {
+        u8::from_str_radix(digits, self.radix()).ok()
+    }
+}
+
+/// Lines `dump` replaces by a single `*` line when they repeat.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Autoskip {
+    /// Squeeze all identical lines
+    Identical,
+    /// Squeeze only identical lines consisting of NUL octets
+    Nul,
 }
 
-#[derive(Debug)]
+/// Layouts of the dumps `reverse` reads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DumpLayout {
+    /// Detect the layout from the first lines
+    Auto,
+    /// The layout of the dump command, which is also the one of xxd
+    #[value(alias = "xxd")]
+    Hexxer,
+    /// hexdump -C
+    HexdumpC,
+    /// hexdump -x and hexdump without options
+    HexdumpX,
+    /// od -A x -t x1
+    Od,
+    /// gdb x/Nxb, addresses are taken relative to the first one
+    Gdb,
+    /// Wireshark hex export, packets are placed one after another
+    Wireshark,
+    /// tcpdump -X, packets are placed one after another
+    Tcpdump,
+}
+
+#[derive(Debug, Clone, Copy)]
 pub enum SubCommand {
     Dump,
     Generate,
     Reverse,
+    Decode,
+    Inspect,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Config {
+    pub align: Option<u32>,
+    pub align_style: AlignStyle,
+    pub append: bool,
+    pub array: bool,
+    pub autoskip: Option<Autoskip>,
+    pub byte_string: bool,
     pub capitalize: bool,
     pub color_choice: ColorChoice,
+    pub color_depth: ColorDepth,
+    pub color_rules: Vec<ColorRule>,
     pub colors: LineColorConfig,
     pub cols: u16,
+    pub const_qualifier: bool,
+    pub constexpr: bool,
     pub decimal_offset: bool,
+    pub dump_template: bool,
+    pub element_type: ElementType,
     pub format: Format,
     pub grouping: u16,
+    pub header: Option<PathBuf>,
+    pub inline: bool,
+    pub highlights: Vec<Highlight>,
     pub input: Option<PathBuf>,
     pub language: Language,
+    pub layout: DumpLayout,
     pub length: usize,
+    pub little_endian: bool,
+    pub namespace: Option<String>,
     pub var_name: String,
     pub offset: usize,
+    pub output: Option<PathBuf>,
+    pub padding: Padding,
+    pub patch: Option<PathBuf>,
     pub plain: bool,
+    pub pragma_once: bool,
+    /// `dump --print-color-table`: the themes are written instead of a dump.
+    pub print_color_table: bool,
+    pub progmem: bool,
+    pub section: Option<String>,
     pub seek: i64,
     pub subcommand: SubCommand,
+    pub template: Option<PathBuf>,
     pub show_offset: bool,
     pub show_text: bool,
+    pub static_storage: bool,
+    pub uppercase: bool,
+    pub value_types: Vec<ValueType>,
     pub vector: bool,
+    /// Octets per element of a generated array.
+    pub word_size: usize,
+}
+
+/// Returns the value of an argument only if it was given on the command line, so the
+/// configuration file can take precedence over the default value.
+fn cli_value<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, id: &str) -> Option<T> {
+    match matches.value_source(id) {
+        Some(ValueSource::CommandLine) => matches.get_one::<T>(id).cloned(),
+        _ => None,
+    }
 }
 
-fn parse_dump(matches: &ArgMatches, config: &mut Config) {
+fn parse_dump(matches: &ArgMatches, config: &mut Config, file: &ConfigFile) -> io::Result<()> {
+    let color_choice = cli_value::<ColorChoice>(matches, "color")
+        .or(file.dump.color)
+        .unwrap_or(ColorChoice::Auto);
     config.subcommand = SubCommand::Dump;
+    config.color_choice = color_choice;
+    // the table goes to the output like a dump, so it honors --output
+    if matches.get_flag("print-color-table") {
+        config.print_color_table = true;
+        return Ok(());
+    }
 
     config.input = matches.get_one::<String>("infile").map(PathBuf::from);
     config.plain = matches.get_flag("plain");
-    config.cols = matches
-        .get_one::<u16>("cols")
+    config.cols = matches.get_one::<u16>("cols").copied().unwrap_or(if config.plain {
+        file.dump.plain_cols.unwrap_or(30)
+    } else {
+        file.dump.cols.unwrap_or(16)
+    });
+    config.format = cli_value::<Format>(matches, "format")
+        .or(file.dump.format)
+        .unwrap_or(Format::Hexadecimal);
+    config.little_endian = matches.get_flag("little-endian");
+    config.value_types = matches
+        .get_many::<ValueType>("type")
+        .unwrap_or_default()
         .copied()
-        .unwrap_or(if config.plain { 30 } else { 16 });
-    config.format = matches
-        .get_one::<Format>("format")
-        .expect("Invalid format choice")
-        .to_owned();
-    config.grouping = matches.get_one::<u16>("grouping").copied().unwrap_or(2u16);
+        .collect();
+    // values are shown below the octets they are made of, so every octet gets a column
+    config.grouping = if config.value_types.is_empty() {
+        matches
+            .get_one::<u16>("grouping")
+            .copied()
+            .or(file.dump.grouping)
+            .unwrap_or(if config.little_endian { 4 } else { 2 })
+    } else {
+        1
+    };
+    // like xxd, a little-endian group is a word
+    if config.little_endian && config.grouping > 0 && !config.grouping.is_power_of_two() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "number of octets per group must be a power of 2 with -e",
+        ));
+    }
+    if let Some(value_type) = config
+        .value_types
+        .iter()
+        .find(|value_type| !usize::from(config.cols).is_multiple_of(value_type.size()))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "--cols {} is not a multiple of the {} octets of {value_type}",
+                config.cols,
+                value_type.size()
+            ),
+        ));
+    }
     config.seek = matches.get_one::<i64>("seek").copied().unwrap_or(0i64);
     config.offset = matches
         .get_one::<usize>("display_offset")
@@ -98,14 +291,46 @@ fn parse_dump(matches: &ArgMatches, config: &mut Config) {
         .unwrap_or(usize::MAX);
     config.show_offset = !matches.get_flag("no-offset");
     config.show_text = !matches.get_flag("no-text");
+    config.autoskip = matches.get_one::<Autoskip>("autoskip").copied();
     config.decimal_offset = matches.get_flag("decimal-offset");
-    config.color_choice = matches
-        .get_one::<ColorChoice>("color")
-        .expect("Invalid color choice")
-        .to_owned();
+    config.uppercase = !matches.get_flag("no-uppercase")
+        && (matches.get_flag("uppercase") || file.dump.uppercase.unwrap_or(false));
+    config.colors = cli_value::<Theme>(matches, "theme")
+        .or(file.dump.theme)
+        .unwrap_or(Theme::Dark)
+        .colors();
+    // rules given later win, so the command line overrides the configuration file
+    config.color_rules = file.color_rules()?;
+    config.color_rules.extend(
+        matches
+            .get_many::<ColorRule>("color-rule")
+            .unwrap_or_default()
+            .copied(),
+    );
+    // annotations come first, so highlights given on the command line win where they overlap
+    config.highlights = match matches.get_one::<PathBuf>("annotations") {
+        Some(path) => annotations::load(path)?,
+        None => Vec::new(),
+    };
+    config.highlights.extend(
+        matches
+            .get_many::<Highlight>("highlight")
+            .unwrap_or_default()
+            .chain(
+                matches
+                    .get_many::<Highlight>("highlight-pattern")
+                    .unwrap_or_default(),
+            )
+            .cloned(),
+    );
+    Ok(())
 }
 
-fn parse_generate(matches: &ArgMatches, config: &mut Config) -> io::Result<()> {
+fn parse_generate(
+    matches: &ArgMatches,
+    config: &mut Config,
+    file: &ConfigFile,
+) -> io::Result<()> {
     if matches.get_flag("list-languages") {
         let langs: Vec<_> = Language::value_variants()
             .iter()
@@ -120,17 +345,30 @@ fn parse_generate(matches: &ArgMatches, config: &mut Config) -> io::Result<()> {
     config.subcommand = SubCommand::Generate;
 
     config.input = matches.get_one::<String>("infile").map(PathBuf::from);
-    config.cols = matches.get_one::<u16>("cols").copied().unwrap_or(12);
+    config.word_size = matches
+        .get_one::<String>("word-size")
+        .map(|bits| bits.parse::<usize>().expect("possible values are numbers") / 8)
+        .expect("word-size has a default");
+    // about as wide as 12 octets
+    config.cols = matches
+        .get_one::<u16>("cols")
+        .copied()
+        .or(file.generate.cols)
+        .unwrap_or(match config.word_size {
+            1 => 12,
+            2 => 8,
+            4 => 6,
+            _ => 4,
+        });
     config.capitalize = matches.get_flag("capitalize");
     config.seek = matches.get_one::<i64>("seek").copied().unwrap_or(0i64);
     config.length = matches
         .get_one::<usize>("length")
         .copied()
         .unwrap_or(usize::MAX);
-    config.language = matches
-        .get_one::<Language>("language")
-        .expect("Invalid language choice")
-        .to_owned();
+    config.language = cli_value::<Language>(matches, "language")
+        .or(file.generate.language)
+        .unwrap_or(Language::C);
     config.var_name = matches
         .get_one::<String>("name")
         .unwrap_or(
@@ -144,51 +382,248 @@ fn parse_generate(matches: &ArgMatches, config: &mut Config) -> io::Result<()> {
         )
         .clone(); // TODO: check how to get rid of clone
     config.vector = matches.get_flag("vector");
+    config.uppercase = !matches.get_flag("no-uppercase")
+        && (matches.get_flag("uppercase") || file.generate.uppercase.unwrap_or(false));
+    config.header = matches.get_one::<PathBuf>("header").cloned();
+    config.pragma_once = matches.get_flag("pragma-once");
+    config.namespace = matches.get_one::<String>("namespace").cloned();
+    config.static_storage = matches.get_flag("static");
+    config.byte_string = matches.get_flag("byte-string");
+    config.align = matches.get_one::<u32>("align").copied();
+    config.align_style = *matches
+        .get_one::<AlignStyle>("align-style")
+        .expect("align-style has a default");
+    config.const_qualifier = matches.get_flag("const");
+    config.constexpr = matches.get_flag("constexpr");
+    config.inline = matches.get_flag("inline");
+    config.section = matches.get_one::<String>("section").cloned();
+    config.progmem = matches.get_flag("progmem");
+    config.element_type = *matches
+        .get_one::<ElementType>("element-type")
+        .expect("element-type has a default");
+    config.little_endian = !matches.get_flag("big-endian");
+    config.padding = *matches
+        .get_one::<Padding>("padding")
+        .expect("padding has a default");
+
+    let invalid = |message: &str| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    // options given for a language they don't apply to
+    let (c, cpp, rust) = (Language::C, Language::Cpp, Language::Rust);
+    let options: [(bool, &str, &[Language]); 12] = [
+        (config.header.is_some(), "--header", &[c, cpp]),
+        (config.namespace.is_some(), "--namespace", &[cpp]),
+        (config.static_storage, "--static", &[c, cpp, rust]),
+        (config.byte_string, "--byte-string", &[rust]),
+        (config.align.is_some(), "--align", &[c, cpp, rust]),
+        (config.const_qualifier, "--const", &[c, cpp]),
+        (config.constexpr, "--constexpr", &[c, cpp]),
+        (config.inline, "--inline", &[cpp]),
+        (config.section.is_some(), "--section", &[c, cpp]),
+        (config.progmem, "--progmem", &[c, cpp]),
+        (
+            config.element_type != ElementType::Uint8T,
+            "--element-type",
+            &[c, cpp],
+        ),
+        (
+            config.element_type == ElementType::StdByte,
+            "--element-type std::byte",
+            &[cpp],
+        ),
+    ];
+    for (given, option, languages) in options {
+        if given && !languages.contains(&config.language) {
+            let names: Vec<String> = languages
+                .iter()
+                .map(|language| format!("-L {language}"))
+                .collect();
+            let names = match names.split_last() {
+                Some((last, rest)) if !rest.is_empty() => format!("{} and {last}", rest.join(", ")),
+                _ => names.join(""),
+            };
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{option} is only supported with {names}"),
+            ));
+        }
+    }
+    if config.header.is_some() {
+        if config.input.is_none() {
+            return invalid("--header needs an input file, the size of stdin is not known");
+        }
+        if config.static_storage || config.inline || config.constexpr {
+            return invalid(
+                "--static, --inline and --constexpr can't be used with --header, which defines the array in the source",
+            );
+        }
+    }
+    if config.word_size > 1 {
+        if config.element_type != ElementType::Uint8T {
+            return invalid("--element-type only applies to arrays of octets, not to --word-size");
+        }
+        if config.byte_string {
+            return invalid("--byte-string can't be used with --word-size");
+        }
+    }
+    if config.constexpr && config.vector {
+        return invalid("--constexpr can't be used with --vector, a std::vector is no literal type");
+    }
+    if config.align.is_some_and(|align| !align.is_power_of_two() || align > 1 << 29) {
+        return invalid("--align must be a power of two up to 2^29");
+    }
     Ok(())
 }
 
-fn parse_reverse(matches: &ArgMatches, config: &mut Config) {
+/// `reverse` reads dumps made with the defaults of the dump command.
+fn parse_reverse(matches: &ArgMatches, config: &mut Config, file: &ConfigFile) {
     config.subcommand = SubCommand::Reverse;
 
     config.input = matches.get_one::<String>("infile").map(PathBuf::from);
+    config.patch = matches.get_one::<String>("patch").map(PathBuf::from);
+    config.array = matches.get_flag("array");
+    config.layout = matches
+        .get_one::<DumpLayout>("from")
+        .expect("Invalid layout choice")
+        .to_owned();
     config.plain = matches.get_flag("plain");
+    config.cols = matches
+        .get_one::<u16>("cols")
+        .copied()
+        .or(file.dump.cols)
+        .unwrap_or(16);
+    config.format = cli_value::<Format>(matches, "format")
+        .or(file.dump.format)
+        .unwrap_or(Format::Hexadecimal);
+    config.little_endian = matches.get_flag("little-endian");
+    config.decimal_offset = matches.get_flag("decimal-offset");
+    config.seek = matches.get_one::<i64>("seek").copied().unwrap_or(0i64);
     config.length = matches
         .get_one::<usize>("length")
         .copied()
-        .unwrap_or(0usize);
+        .unwrap_or(usize::MAX);
+}
+
+fn parse_decode(matches: &ArgMatches, config: &mut Config, file: &ConfigFile) {
+    config.subcommand = SubCommand::Decode;
+
+    config.input = matches.get_one::<String>("infile").map(PathBuf::from);
+    config.template = matches.get_one::<PathBuf>("template").cloned();
+    config.seek = matches.get_one::<i64>("seek").copied().unwrap_or(0i64);
+    config.length = matches
+        .get_one::<usize>("length")
+        .copied()
+        .unwrap_or(usize::MAX);
+
+    // the dump of the decoded bytes uses the defaults of the dump command
+    config.dump_template = matches.get_flag("dump");
+    config.cols = file.dump.cols.unwrap_or(16);
+    config.grouping = file.dump.grouping.unwrap_or(2);
+    config.format = file.dump.format.unwrap_or(Format::Hexadecimal);
+    config.uppercase = file.dump.uppercase.unwrap_or(false);
+    config.show_offset = true;
+    config.show_text = true;
+    config.color_choice = cli_value::<ColorChoice>(matches, "color")
+        .or(file.dump.color)
+        .unwrap_or(ColorChoice::Auto);
+    config.colors = file.dump.theme.unwrap_or(Theme::Dark).colors();
+}
+
+fn parse_inspect(matches: &ArgMatches, config: &mut Config) {
+    config.subcommand = SubCommand::Inspect;
+
+    config.input = matches.get_one::<String>("infile").map(PathBuf::from);
+    config.seek = matches.get_one::<i64>("at").copied().unwrap_or(0i64);
 }
 
 impl Config {
+    /// The position `--seek` moves the input to. A negative seek counts from the end of the file.
+    pub fn seek_position(&self) -> io::Result<u64> {
+        if self.seek >= 0 {
+            return Ok(self.seek.unsigned_abs());
+        }
+        let input = self.input.as_ref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seeking to negative offsets is not supported when reading from stdin",
+            )
+        })?;
+        Ok(fs::metadata(input)?
+            .len()
+            .saturating_sub(self.seek.unsigned_abs()))
+    }
+
     pub fn new() -> Result<Self, io::Error> {
         let cli = parse_cli();
+        let file = file::load(cli.get_one::<PathBuf>("config").map(PathBuf::as_path))?;
         let mut config = Config {
+            align: None,
+            align_style: AlignStyle::Standard,
+            append: false,
+            array: false,
+            autoskip: None,
+            byte_string: false,
             capitalize: false,
             cols: 0,
+            const_qualifier: false,
+            constexpr: false,
             color_choice: ColorChoice::Auto,
+            color_depth: ColorDepth::None,
+            color_rules: Vec::new(),
             colors: LineColorConfig::default(),
             decimal_offset: false,
+            dump_template: false,
+            element_type: ElementType::Uint8T,
             format: Format::Hexadecimal,
             grouping: 0,
+            header: None,
+            inline: false,
+            highlights: Vec::new(),
             input: None,
             language: Language::C,
+            layout: DumpLayout::Auto,
             length: usize::MAX,
+            little_endian: false,
+            namespace: None,
             var_name: String::new(),
+            patch: None,
             plain: false,
+            pragma_once: false,
+            print_color_table: false,
+            progmem: false,
+            section: None,
             seek: 0,
             subcommand: SubCommand::Dump,
+            template: None,
             offset: 0,
+            output: None,
+            padding: Padding::Zeros,
             show_offset: false,
             show_text: false,
+            static_storage: false,
+            uppercase: false,
+            value_types: Vec::new(),
             vector: false,
+            word_size: 1,
         };
 
         match cli.subcommand() {
-            Some(("dump", dump)) => parse_dump(dump, &mut config),
-            Some(("generate", generate)) => parse_generate(generate, &mut config)?,
-            Some(("reverse", reverse)) => parse_reverse(reverse, &mut config),
+            Some(("dump", dump)) => parse_dump(dump, &mut config, &file)?,
+            Some(("generate", generate)) => parse_generate(generate, &mut config, &file)?,
+            Some(("reverse", reverse)) => parse_reverse(reverse, &mut config, &file),
+            Some(("decode", decode)) => parse_decode(decode, &mut config, &file),
+            Some(("inspect", inspect)) => parse_inspect(inspect, &mut config),
             _ => process::exit(0), // we should never get here
         }
 
+        file.apply_colors(&mut config.colors)?;
+        config.output = cli.get_one::<String>("output").map(PathBuf::from);
+        config.append = cli.get_flag("append");
+        // color escapes only make sense on a terminal
+        if config.output.is_some() && matches!(config.color_choice, ColorChoice::Auto) {
+            config.color_choice = ColorChoice::Never;
+        }
+        config.color_depth = ColorDepth::detect(config.color_choice);
+
         Ok(config)
     }
 }
@@ -220,6 +655,32 @@ options:
 "
         )
         .subcommand_required(true)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .help(format!("Read defaults and colors from <file> instead of $XDG_CONFIG_HOME/hexxer/config.toml. Can also be set with {}.", file::CONFIG_ENV))
+                .num_args(1)
+                .value_name("file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('O')
+                .long("output")
+                .help("Write to <file> instead of stdout.")
+                .num_args(1)
+                .value_name("file")
+                .global(true),
+        )
+        .arg(
+            Arg::new("append")
+                .long("append")
+                .help("Append to the output file instead of truncating it.")
+                .requires("output")
+                .action(clap::ArgAction::SetTrue)
+                .global(true),
+        )
         .subcommand(
             Command::new("dump")
                 .about("Dump a file to the terminal")
@@ -241,6 +702,18 @@ options:
                         .help( "Don't show the text part.")
                         .action(clap::ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("autoskip")
+                        .short('a')
+                        .long("autoskip")
+                        .help("Replace repeated lines by a single '*' line. [default: identical]")
+                        .num_args(0..=1)
+                        .value_name("lines")
+                        .default_missing_value("identical")
+                        .require_equals(true)
+                        .conflicts_with("plain")
+                        .value_parser(value_parser!(Autoskip)),
+                )
                 .arg(
                     Arg::new("decimal-offset")
                         .short('d')
@@ -254,6 +727,18 @@ options:
                         .num_args(1)
                         .value_parser(clap::value_parser!(u16)),
                 )
+                .arg(
+                    arg!( -u --uppercase "Use upper case hex letters.")
+                        .overrides_with("no-uppercase")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-uppercase")
+                        .long("no-uppercase")
+                        .help("Use lower case hex letters, even if the configuration file sets uppercase.")
+                        .overrides_with("uppercase")
+                        .action(clap::ArgAction::SetTrue),
+                )
                 .arg(
                     arg!( -f --format <format> "Dump format.")
                         .num_args(1)
@@ -264,11 +749,30 @@ options:
                     Arg::new("grouping")
                         .short('g')
                         .long("grouping")
-                        .help("Number of octets per group. [default: 2] Not compatible with -P/--plain.")
+                        .help("Number of octets per group. [default: 2 (-e/--little-endian: 4)] A power of 2 with -e/--little-endian. Not compatible with -P/--plain.")
                         .num_args(1)
                         .conflicts_with("plain")
                         .value_parser(clap::value_parser!(u16)),
                 )
+                .arg(
+                    Arg::new("little-endian")
+                        .short('e')
+                        .long("little-endian")
+                        .help("Show every group as a little-endian word. The text part keeps the file order.")
+                        .conflicts_with("plain")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("type")
+                        .short('t')
+                        .long("type")
+                        .help("Show the octets of every line as values of <type> in a row below them, like od -t: u8 to u64, i8 to i64, f32 or f64 with an optional le or be suffix, little-endian by default. Can be given multiple times. The dump can't be reversed.")
+                        .num_args(1)
+                        .value_name("type")
+                        .conflicts_with_all(["plain", "grouping", "little-endian"])
+                        .action(clap::ArgAction::Append)
+                        .value_parser(ValueType::from_str),
+                )
                 .arg(
                     arg!(-s --seek <offset> "Seek to <offset> before dumping.")
                         .num_args(1)
@@ -294,6 +798,56 @@ options:
                         .default_value("auto")
                         .value_parser(value_parser!(ColorChoice)),
                 )
+                .arg(
+                    Arg::new("theme")
+                        .long("theme")
+                        .help("Color theme.")
+                        .num_args(1)
+                        .value_name("theme")
+                        .default_value("dark")
+                        .value_parser(value_parser!(Theme)),
+                )
+                .arg(
+                    Arg::new("color-rule")
+                        .long("color-rule")
+                        .help("Color the byte values <bytes>, e.g. 0xff or 0x80-0xbf, and optionally show them as <glyph> in the text column. Can be given multiple times, later rules win.")
+                        .num_args(1)
+                        .value_name("bytes=[color][:glyph]")
+                        .action(clap::ArgAction::Append)
+                        .value_parser(ColorRule::from_str),
+                )
+                .arg(
+                    Arg::new("highlight")
+                        .long("highlight")
+                        .help("Highlight the bytes at <offset>, <offset>..<end> or <offset>+<len>, as shown in the dump, with an optional background color. Can be given multiple times.")
+                        .num_args(1)
+                        .value_name("offset[..end|+len][:color]")
+                        .action(clap::ArgAction::Append)
+                        .value_parser(Highlight::range),
+                )
+                .arg(
+                    Arg::new("highlight-pattern")
+                        .long("highlight-pattern")
+                        .help("Highlight every occurrence of hex bytes like 0xdeadbeef or of a string. Can be given multiple times.")
+                        .num_args(1)
+                        .value_name("hex|string")
+                        .action(clap::ArgAction::Append)
+                        .value_parser(Highlight::pattern),
+                )
+                .arg(
+                    Arg::new("annotations")
+                        .long("annotations")
+                        .help("Color the regions listed in a JSON or TOML file and show their labels next to the lines they start on.")
+                        .num_args(1)
+                        .value_name("file")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("print-color-table")
+                        .long("print-color-table")
+                        .help("Print a sample of every byte class in each theme and exit.")
+                        .action(clap::ArgAction::SetTrue),
+                )
         )
         .subcommand(
             Command::new("generate")
@@ -344,10 +898,143 @@ options:
                         .exclusive(true)
                 )
                 .arg(
-                    arg!( -v --vector "Generate a vector(dynmaic array) instead of an array if applicable to the language.")
+                    arg!( -u --uppercase "Use upper case hex letters.")
+                        .overrides_with("no-uppercase")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-uppercase")
+                        .long("no-uppercase")
+                        .help("Use lower case hex letters, even if the configuration file sets uppercase.")
+                        .overrides_with("uppercase")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!( -v --vector "Generate a vector(dynmaic array) instead of an array if applicable to the language. A &[u8] slice in Rust, a slice in Zig and D and a seq in Nim.")
                         .default_value("false")
                         .action(clap::ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("header")
+                        .long("header")
+                        .help("Write the declarations of the array and its length to the header <file>, which the generated source includes. C and C++ only.")
+                        .num_args(1)
+                        .value_name("file")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("pragma-once")
+                        .long("pragma-once")
+                        .help("Use #pragma once instead of an include guard in the header.")
+                        .requires("header")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("static")
+                        .long("static")
+                        .help("Declare the array static: a static instead of a constant in Rust, internal linkage in C and C++.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("byte-string")
+                        .long("byte-string")
+                        .help("Write the octets as a byte string literal like b\"\\x89PNG\". Rust only.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("align")
+                        .long("align")
+                        .help("Align the array to <bytes>. In Rust the array is wrapped in a #[repr(align)] struct.")
+                        .num_args(1)
+                        .value_name("bytes")
+                        .conflicts_with("vector")
+                        .value_parser(clap::value_parser!(u32)),
+                )
+                .arg(
+                    Arg::new("align-style")
+                        .long("align-style")
+                        .help("How --align aligns C and C++ arrays.")
+                        .num_args(1)
+                        .value_name("style")
+                        .default_value("standard")
+                        .requires("align")
+                        .value_parser(value_parser!(AlignStyle)),
+                )
+                .arg(
+                    Arg::new("const")
+                        .long("const")
+                        .help("Declare the array const, so it can be placed in flash. C and C++ only.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("constexpr")
+                        .long("constexpr")
+                        .help("Declare the array constexpr. C++ and C23 only.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("inline")
+                        .long("inline")
+                        .help("Declare the array inline, so the code can be put into a header included several times. C++17 only.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("section")
+                        .long("section")
+                        .help("Place the array in the linker section <name> with __attribute__((section)). C and C++ only.")
+                        .num_args(1)
+                        .value_name("name")
+                        .value_parser(clap::value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("progmem")
+                        .long("progmem")
+                        .help("Place the const array in the program memory of AVR microcontrollers with PROGMEM. C and C++ only.")
+                        .conflicts_with("section")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("element-type")
+                        .long("element-type")
+                        .help("Type of the array elements. C and C++ only.")
+                        .num_args(1)
+                        .value_name("type")
+                        .default_value("uint8_t")
+                        .value_parser(value_parser!(ElementType)),
+                )
+                .arg(
+                    Arg::new("word-size")
+                        .long("word-size")
+                        .help("Pack the input into elements of <bits>. [default: 8] --cols counts elements.")
+                        .num_args(1)
+                        .value_name("bits")
+                        .default_value("8")
+                        .hide_default_value(true)
+                        .value_parser(["8", "16", "32", "64"]),
+                )
+                .arg(
+                    Arg::new("big-endian")
+                        .long("big-endian")
+                        .help("Read the elements of --word-size as big-endian instead of little-endian words.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("padding")
+                        .long("padding")
+                        .help("What to do with the octets of a trailing partial word.")
+                        .num_args(1)
+                        .value_name("policy")
+                        .default_value("zeros")
+                        .value_parser(value_parser!(Padding)),
+                )
+                .arg(
+                    Arg::new("namespace")
+                        .long("namespace")
+                        .help("Put the array into the C++ namespace <name>, e.g. fw or fw::assets.")
+                        .num_args(1)
+                        .value_name("name")
+                        .value_parser(clap::value_parser!(String)),
+                )
         )
         .subcommand(
             // Attention: xxd  reverse actually takes the offsets into account and does not blindly read
@@ -360,11 +1047,61 @@ options:
                     arg!( -p --plain "Plain text (hex only).")
                         .action(clap::ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("little-endian")
+                        .short('e')
+                        .long("little-endian")
+                        .help("Read every group as a little-endian word, as dump -e/--little-endian shows it.")
+                        .conflicts_with("plain")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!( -a --array "Read a source code array as generated by the generate command.")
+                        .conflicts_with_all(["plain", "format", "cols", "decimal-offset", "little-endian"])
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .help("Layout of the dump, detected from the first lines by default. --format, --cols and --decimal-offset only apply to the hexxer layout.")
+                        .num_args(1)
+                        .value_name("layout")
+                        .default_value("auto")
+                        .conflicts_with_all(["plain", "array"])
+                        .value_parser(value_parser!(DumpLayout)),
+                )
+                .arg(
+                    arg!(-c --cols <columns> "Read at most <columns> octets per line. [default: 16] A value of 0 reads 32 octets, like dump -c 0 writes them.")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(u16)),
+                )
+                .arg(
+                    arg!( -f --format <format> "Format the dump was made with.")
+                        .num_args(1)
+                        .default_value("hexadecimal")
+                        .value_parser(value_parser!(Format)),
+                )
+                .arg(
+                    Arg::new("decimal-offset")
+                        .short('d')
+                        .long("decimal-offset")
+                        .help("Read offsets as decimal instead of hex.")
+                        .default_value("false")
+                        .action(clap::ArgAction::SetTrue),
+                )
                 .arg(
                     arg!(-l --length <length> "Stop after <length> octets.")
                         .num_args(1)
                         .value_parser(clap::value_parser!(usize)),
                 )
+                .arg(
+                    Arg::new("patch")
+                        .long("patch")
+                        .help("Patch <file> in place instead of writing to stdout. Only the octets found in the dump are overwritten, the file is not truncated.")
+                        .num_args(1)
+                        .value_name("file")
+                        .conflicts_with("output"),
+                )
                 .arg(
                     arg!(-s --seek <offset> "Add <offset> to the file positions found in the infile before appplying.")
                         .num_args(1)
@@ -372,5 +1109,60 @@ options:
                         .value_parser(clap::value_parser!(i64)),
                 )
         )
+        .subcommand(
+            Command::new("decode")
+                .about("Decode a binary layout with a template.")
+                .visible_alias("dec")
+                .arg(input_arg.clone())
+                .arg(
+                    Arg::new("template")
+                        .short('t')
+                        .long("template")
+                        .help("TOML file describing the fields to decode.")
+                        .num_args(1)
+                        .value_name("file")
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(-s --seek <offset> "Seek to <offset> before decoding.")
+                        .num_args(1)
+                        .allow_negative_numbers(true)
+                        .value_parser(clap::value_parser!(i64)),
+                )
+                .arg(
+                    arg!(-l --length <length> "Decode at most <length> octets.")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("dump")
+                        .long("dump")
+                        .help("Dump the decoded octets after the fields, with every field colored and labeled.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("color")
+                        .long("color")
+                        .alias("colour")
+                        .help("Color the dump. [default: auto]")
+                        .num_args(1)
+                        .value_name("when")
+                        .default_value("auto")
+                        .value_parser(value_parser!(ColorChoice)),
+                )
+        )
+        .subcommand(
+            Command::new("inspect")
+                .about("Show the octets at an offset as every common type.")
+                .visible_alias("ins")
+                .arg(input_arg.clone())
+                .arg(
+                    arg!(-a --at <offset> "Inspect the octets at <offset>, negative offsets count from the end.")
+                        .num_args(1)
+                        .allow_negative_numbers(true)
+                        .value_parser(clap::value_parser!(i64)),
+                )
+        )
         .get_matches()
 }
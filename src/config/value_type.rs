@@ -0,0 +1,132 @@
+use crate::bytes;
+
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Number {
+    Unsigned,
+    Signed,
+    Float,
+}
+
+/// A type the octets of a dump line are shown as in a row of values below them, like the types
+/// of `od -t`: `u8` to `u64`, `i8` to `i64`, `f32` or `f64`, with an `le` or `be` suffix for the
+/// byte order. Little-endian if no suffix is given.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ValueType {
+    number: Number,
+    /// Octets per value.
+    size: usize,
+    big_endian: bool,
+}
+
+impl ValueType {
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Characters the widest value of the type takes.
+    pub fn width(&self) -> usize {
+        match (self.number, self.size) {
+            (Number::Unsigned, 1) => 3,
+            (Number::Signed, 1) => 4,
+            (Number::Unsigned, 2) => 5,
+            (Number::Signed, 2) => 6,
+            (Number::Unsigned, 4) => 10,
+            (Number::Signed, 4) => 11,
+            (Number::Float, 4) => 14,
+            (Number::Float, _) => 24,
+            _ => 20,
+        }
+    }
+
+    /// Formats the value of the first `size` octets of `bytes`.
+    pub fn value(&self, bytes: &[u8]) -> String {
+        let value = bytes::word_value(&bytes[..self.size], !self.big_endian);
+
+        match (self.number, self.size) {
+            (Number::Unsigned, _) => value.to_string(),
+            (Number::Signed, _) => {
+                // sign extend from the size of the value
+                let shift = 64 - 8 * self.size;
+                ((value << shift) as i64 >> shift).to_string()
+            }
+            // scientific notation only where the digits would not fit the width
+            (Number::Float, 4) => {
+                let float = f32::from_bits(value as u32);
+                let magnitude = float.abs();
+                if magnitude == 0.0 || !magnitude.is_finite() || (1e-4..1e7).contains(&magnitude) {
+                    float.to_string()
+                } else {
+                    format!("{float:e}")
+                }
+            }
+            (Number::Float, _) => {
+                let float = f64::from_bits(value);
+                let magnitude = float.abs();
+                if magnitude == 0.0 || !magnitude.is_finite() || (1e-4..1e16).contains(&magnitude) {
+                    float.to_string()
+                } else {
+                    format!("{float:e}")
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = match self.number {
+            Number::Unsigned => 'u',
+            Number::Signed => 'i',
+            Number::Float => 'f',
+        };
+        write!(f, "{number}{}", self.size * 8)?;
+        match (self.size, self.big_endian) {
+            (1, _) => Ok(()),
+            (_, true) => f.write_str("be"),
+            (_, false) => f.write_str("le"),
+        }
+    }
+}
+
+impl FromStr for ValueType {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid type '{spec}', expected u8 to u64, i8 to i64, f32 or f64 with an optional le or be suffix"
+            )
+        };
+        let name = spec.trim().to_ascii_lowercase();
+        let (name, big_endian) = if let Some(name) = name.strip_suffix("le") {
+            (name, false)
+        } else if let Some(name) = name.strip_suffix("be") {
+            (name, true)
+        } else {
+            (name.as_str(), false)
+        };
+
+        let mut chars = name.chars();
+        let number = match chars.next() {
+            Some('u') => Number::Unsigned,
+            Some('i') => Number::Signed,
+            Some('f') => Number::Float,
+            _ => return Err(invalid()),
+        };
+        let size = match (number, chars.as_str()) {
+            (Number::Unsigned | Number::Signed, "8") => 1,
+            (Number::Unsigned | Number::Signed, "16") => 2,
+            (_, "32") => 4,
+            (_, "64") => 8,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            number,
+            size,
+            big_endian,
+        })
+    }
+}
@@ -0,0 +1,576 @@
+mod schema;
+
+use crate::bytes;
+use crate::config::Config;
+use crate::config::highlight::{Highlight, Target};
+use crate::dump;
+use schema::{Endian, Field, Integer, Kind, Length, Schema, Size};
+
+use std::io::{self, Cursor, Read, Write};
+
+use owo_colors::DynColors;
+
+/// Structs nested deeper than this are most likely a struct containing itself.
+const MAX_DEPTH: usize = 64;
+/// Guards against counts read from garbage, which would decode for ages.
+const MAX_NODES: usize = 1_000_000;
+/// Raw bytes shown per field, longer fields are cut.
+const RAW_BYTES: usize = 8;
+/// Bytes of a byte array shown as its value.
+const VALUE_BYTES: usize = 32;
+
+/// A decoded field: its position, type and value, or the fields it is made of.
+struct Node {
+    name: String,
+    type_name: String,
+    offset: usize,
+    len: usize,
+    value: Option<String>,
+    color: Option<DynColors>,
+    children: Vec<Node>,
+}
+
+struct Decoder<'a> {
+    schema: &'a Schema,
+    data: &'a [u8],
+    /// The offset in the file of the first byte of `data`.
+    base: usize,
+    position: usize,
+    /// The integer fields decoded so far, per struct being decoded, so counts and lengths can
+    /// refer to them.
+    scopes: Vec<Vec<(&'a str, i128)>>,
+    nodes: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(schema: &'a Schema, data: &'a [u8], base: usize) -> Self {
+        Self {
+            schema,
+            data,
+            base,
+            position: 0,
+            scopes: Vec::new(),
+            nodes: 0,
+        }
+    }
+
+    fn take(&mut self, len: usize, field: &Field) -> Result<&'a [u8], String> {
+        let left = self.data.len() - self.position;
+        if len > left {
+            return Err(format!(
+                "field '{}' at offset {:#x} needs {len} bytes, only {left} left",
+                field.name,
+                self.base + self.position
+            ));
+        }
+        let bytes = &self.data[self.position..self.position + len];
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn integer(&mut self, integer: Integer, field: &Field) -> Result<i128, String> {
+        let bytes = self.take(integer.size(), field)?;
+        let value = bytes::word_value(bytes, field.endian == Endian::Little);
+
+        Ok(if integer.is_signed() {
+            // sign extend from the size of the integer
+            let shift = 64 - 8 * integer.size();
+            i128::from((value << shift) as i64 >> shift)
+        } else {
+            i128::from(value)
+        })
+    }
+
+    /// Resolves a count or length, looking up field names from the innermost struct outwards.
+    fn size(&self, size: &Size, field: &Field) -> Result<usize, String> {
+        let value = match size {
+            Size::Fixed(value) => return Ok(*value),
+            Size::Field(name) => self
+                .scopes
+                .iter()
+                .rev()
+                .flat_map(|scope| scope.iter().rev())
+                .find(|(candidate, _)| candidate == name)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| {
+                    format!(
+                        "field '{}' refers to '{name}', which is no earlier integer field",
+                        field.name
+                    )
+                })?,
+        };
+        usize::try_from(value)
+            .map_err(|_| format!("field '{}' has the invalid size {value}", field.name))
+    }
+
+    fn length(&mut self, length: &Length, field: &Field) -> Result<usize, String> {
+        match length {
+            Length::Size(size) => self.size(size, field),
+            Length::Prefix(integer) => {
+                let value = self.integer(*integer, field)?;
+                usize::try_from(value)
+                    .map_err(|_| format!("field '{}' has the invalid length {value}", field.name))
+            }
+        }
+    }
+
+    fn fields(&mut self, fields: &'a [Field], depth: usize) -> Result<Vec<Node>, String> {
+        self.scopes.push(Vec::new());
+        let nodes = fields
+            .iter()
+            .map(|field| self.field(field, depth))
+            .collect();
+        self.scopes.pop();
+        nodes
+    }
+
+    fn field(&mut self, field: &'a Field, depth: usize) -> Result<Node, String> {
+        let Some(count) = &field.count else {
+            return self.element(field, field.name.clone(), depth);
+        };
+
+        let count = self.size(count, field)?;
+        let offset = self.position;
+        let children = (0..count)
+            .map(|i| self.element(field, format!("[{i}]"), depth))
+            .collect::<Result<Vec<Node>, String>>()?;
+
+        Ok(Node {
+            name: field.name.clone(),
+            type_name: format!("{}[{count}]", field.type_name),
+            offset: self.base + offset,
+            len: self.position - offset,
+            value: None,
+            color: field.color,
+            children,
+        })
+    }
+
+    /// Decodes a single value of the type of `field`.
+    fn element(&mut self, field: &'a Field, name: String, depth: usize) -> Result<Node, String> {
+        self.nodes += 1;
+        if self.nodes > MAX_NODES {
+            return Err(format!("more than {MAX_NODES} fields decoded"));
+        }
+
+        let offset = self.position;
+        let mut type_name = field.type_name.clone();
+        let mut children = Vec::new();
+        let value = match &field.kind {
+            Kind::Integer {
+                integer,
+                enumeration,
+            } => {
+                let value = self.integer(*integer, field)?;
+                if field.count.is_none() {
+                    let scope = self.scopes.last_mut().expect("fields push a scope");
+                    scope.push((&field.name, value));
+                }
+                match enumeration
+                    .as_ref()
+                    .and_then(|name| self.schema.enums[name].get(&value))
+                {
+                    Some(value_name) => Some(format!("{value} ({value_name})")),
+                    None => Some(value.to_string()),
+                }
+            }
+            Kind::F32 => {
+                let bytes: [u8; 4] = self.take(4, field)?.try_into().expect("took 4 bytes");
+                Some(
+                    match field.endian {
+                        Endian::Little => f32::from_le_bytes(bytes),
+                        Endian::Big => f32::from_be_bytes(bytes),
+                    }
+                    .to_string(),
+                )
+            }
+            Kind::F64 => {
+                let bytes: [u8; 8] = self.take(8, field)?.try_into().expect("took 8 bytes");
+                Some(
+                    match field.endian {
+                        Endian::Little => f64::from_le_bytes(bytes),
+                        Endian::Big => f64::from_be_bytes(bytes),
+                    }
+                    .to_string(),
+                )
+            }
+            Kind::Bytes(length) => {
+                let len = self.length(length, field)?;
+                type_name = format!("bytes[{len}]");
+                let bytes = self.take(len, field)?;
+                let shown = &bytes[..len.min(VALUE_BYTES)];
+                let cut = if len > VALUE_BYTES { ".." } else { "" };
+                Some(format!("b\"{}\"{cut}", shown.escape_ascii()))
+            }
+            Kind::String(length) => {
+                let len = self.length(length, field)?;
+                type_name = format!("string[{len}]");
+                let bytes = self.take(len, field)?;
+                // fixed length strings are often padded with NULs
+                let text = String::from_utf8_lossy(bytes);
+                Some(format!("{:?}", text.trim_end_matches('\0')))
+            }
+            Kind::Struct(name) => {
+                if depth >= MAX_DEPTH {
+                    return Err(format!(
+                        "structs nested deeper than {MAX_DEPTH} levels at field '{}'",
+                        field.name
+                    ));
+                }
+                children = self.fields(&self.schema.structs[name], depth + 1)?;
+                None
+            }
+        };
+
+        // numbers of more than one byte show their byte order
+        if matches!(&field.kind, Kind::Integer { integer, .. } if integer.size() > 1)
+            || matches!(field.kind, Kind::F32 | Kind::F64)
+        {
+            type_name.push_str(match field.endian {
+                Endian::Little => "le",
+                Endian::Big => "be",
+            });
+        }
+
+        Ok(Node {
+            name,
+            type_name,
+            offset: self.base + offset,
+            len: self.position - offset,
+            value,
+            color: field.color,
+            children,
+        })
+    }
+}
+
+/// A line of the field tree.
+struct Row {
+    offset: usize,
+    name: String,
+    type_name: String,
+    raw: String,
+    value: String,
+}
+
+fn rows(nodes: &[Node], depth: usize, data: &[u8], base: usize, rows: &mut Vec<Row>) {
+    for node in nodes {
+        let raw = if node.children.is_empty() {
+            let bytes = &data[node.offset - base..node.offset - base + node.len];
+            let mut raw: Vec<String> = bytes
+                .iter()
+                .take(RAW_BYTES)
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            if bytes.len() > RAW_BYTES {
+                raw.push("..".to_string());
+            }
+            raw.join(" ")
+        } else {
+            String::new()
+        };
+
+        rows.push(Row {
+            offset: node.offset,
+            name: format!("{}{}", "  ".repeat(depth), node.name),
+            type_name: node.type_name.clone(),
+            raw,
+            value: node.value.clone().unwrap_or_default(),
+        });
+        self::rows(&node.children, depth + 1, data, base, rows);
+    }
+}
+
+/// The fields without children as highlights, labeled with their path.
+fn regions(nodes: &[Node], path: &str, highlights: &mut Vec<Highlight>) {
+    for node in nodes {
+        let path = if node.name.starts_with('[') || path.is_empty() {
+            format!("{path}{}", node.name)
+        } else {
+            format!("{path}.{}", node.name)
+        };
+        if node.children.is_empty() {
+            highlights.push(Highlight {
+                target: Target::Region {
+                    start: node.offset,
+                    end: node.offset + node.len,
+                },
+                color: node.color,
+                label: path,
+            });
+        } else {
+            regions(&node.children, &path, highlights);
+        }
+    }
+}
+
+/// Decodes the input with the template and prints the field tree: offset, name, type, raw bytes
+/// and value of every field. With `--dump` a dump of the decoded bytes follows, with every field
+/// colored and labeled.
+pub fn decode<R: Read, W: Write>(mut reader: R, out: &mut W, config: &Config) -> io::Result<()> {
+    let template = config
+        .template
+        .as_ref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No template file specified"))?;
+    let schema = schema::load(template)?;
+
+    let mut data = Vec::new();
+    reader
+        .by_ref()
+        .take(config.length as u64)
+        .read_to_end(&mut data)?;
+    let base = config.seek_position()? as usize;
+
+    let mut decoder = Decoder::new(&schema, &data, base);
+    let nodes = decoder
+        .fields(&schema.fields, 0)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let end = decoder.position;
+
+    let mut tree = Vec::new();
+    rows(&nodes, 0, &data, base, &mut tree);
+    let name_width = tree.iter().map(|row| row.name.len()).max().unwrap_or(0);
+    let type_width = tree
+        .iter()
+        .map(|row| row.type_name.len())
+        .max()
+        .unwrap_or(0);
+    let raw_width = tree.iter().map(|row| row.raw.len()).max().unwrap_or(0);
+    for row in &tree {
+        let line = format!(
+            "{:08x}  {:name_width$}  {:type_width$}  {:raw_width$}  {}",
+            row.offset, row.name, row.type_name, row.raw, row.value
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    if config.dump_template {
+        let mut dump_config = config.clone();
+        dump_config.offset = base;
+        dump_config.highlights.clear();
+        regions(&nodes, "", &mut dump_config.highlights);
+        writeln!(out)?;
+        dump::dump(Cursor::new(&data[..end]), out, &dump_config)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(template: &str, data: &[u8]) -> Result<Vec<Node>, String> {
+        let schema = schema::parse(template)?;
+        Decoder::new(&schema, data, 0).fields(&schema.fields, 0)
+    }
+
+    /// `<path> = <value>` of every field, depth first.
+    fn values(template: &str, data: &[u8]) -> Vec<String> {
+        fn walk(nodes: &[Node], path: &str, values: &mut Vec<String>) {
+            for node in nodes {
+                let path = format!("{path}{}", node.name);
+                match &node.value {
+                    Some(value) => values.push(format!("{path} = {value}")),
+                    None => walk(&node.children, &format!("{path}."), values),
+                }
+            }
+        }
+
+        let nodes = decode(template, data).expect("decodes");
+        let mut values = Vec::new();
+        walk(&nodes, "", &mut values);
+        values
+    }
+
+    #[test]
+    fn counts_refer_to_earlier_fields() {
+        let template = r#"
+            fields = [
+                { name = "n", type = "u8" },
+                { name = "items", type = "u16", count = "n" },
+            ]
+        "#;
+        assert_eq!(
+            values(template, &[2, 1, 0, 2, 0]),
+            ["n = 2", "items.[0] = 1", "items.[1] = 2"]
+        );
+
+        let template = r#"
+            fields = [{ name = "items", type = "u8", count = "n" }]
+        "#;
+        let error = decode(template, &[1]).err().expect("n is unknown");
+        assert!(error.contains("refers to 'n', which is no earlier integer field"));
+    }
+
+    #[test]
+    fn counts_are_looked_up_in_outer_structs() {
+        let template = r#"
+            fields = [
+                { name = "n", type = "u8" },
+                { name = "entry", type = "entry" },
+            ]
+
+            [structs]
+            entry = [
+                { name = "values", type = "u8", count = "n" },
+                { name = "n", type = "u8" },
+                { name = "names", type = "bytes", length = "n" },
+            ]
+        "#;
+        // the inner n hides the outer one once it is decoded
+        assert_eq!(
+            values(template, &[2, 7, 8, 1, b'x']),
+            [
+                "n = 2",
+                "entry.values.[0] = 7",
+                "entry.values.[1] = 8",
+                "entry.n = 1",
+                "entry.names = b\"x\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn length_prefixed_strings_and_bytes() {
+        let template = r#"
+            endian = "big"
+            fields = [
+                { name = "name", type = "string", length-prefix = "u16" },
+                { name = "blob", type = "bytes", length-prefix = "u8" },
+                { name = "padded", type = "string", length = 4 },
+            ]
+        "#;
+        let data = b"\x00\x03abc\x02\x00\xffhi\0\0";
+        assert_eq!(
+            values(template, data),
+            [
+                "name = \"abc\"",
+                "blob = b\"\\x00\\xff\"",
+                "padded = \"hi\"",
+            ]
+        );
+
+        let nodes = decode(template, data).expect("decodes");
+        let types: Vec<&str> = nodes.iter().map(|node| node.type_name.as_str()).collect();
+        assert_eq!(types, ["string[3]", "bytes[2]", "string[4]"]);
+        // the prefix belongs to the field
+        assert_eq!((nodes[1].offset, nodes[1].len), (5, 3));
+    }
+
+    #[test]
+    fn enum_names() {
+        let template = r#"
+            fields = [{ name = "kind", type = "i8", count = 3, enum = "kind" }]
+
+            [enums.kind]
+            1 = "one"
+            0x10 = "sixteen"
+            -1 = "none"
+        "#;
+        assert_eq!(
+            values(template, &[1, 0x10, 0xff]),
+            [
+                "kind.[0] = 1 (one)",
+                "kind.[1] = 16 (sixteen)",
+                "kind.[2] = -1 (none)",
+            ]
+        );
+        assert_eq!(values(template, &[2, 1, 1])[0], "kind.[0] = 2");
+    }
+
+    #[test]
+    fn nested_structs() {
+        let template = r#"
+            fields = [
+                { name = "magic", type = "u8" },
+                { name = "points", type = "point", count = 2 },
+            ]
+
+            [structs]
+            point = [
+                { name = "x", type = "u8" },
+                { name = "pos", type = "pos" },
+            ]
+            pos = [{ name = "y", type = "u16", endian = "big" }]
+        "#;
+        let nodes = decode(template, &[9, 1, 0, 2, 3, 0, 4]).expect("decodes");
+        let points = &nodes[1];
+        assert_eq!(
+            (points.type_name.as_str(), points.offset, points.len),
+            ("point[2]", 1, 6)
+        );
+        let second = &points.children[1];
+        assert_eq!((second.offset, second.len), (4, 3));
+        let y = &second.children[1].children[0];
+        assert_eq!((y.name.as_str(), y.type_name.as_str()), ("y", "u16be"));
+        assert_eq!(y.value.as_deref(), Some("4"));
+    }
+
+    #[test]
+    fn signed_integers_are_sign_extended() {
+        let template = r#"
+            fields = [
+                { name = "a", type = "i8" },
+                { name = "b", type = "i16" },
+                { name = "c", type = "i32", endian = "big" },
+                { name = "d", type = "i64" },
+                { name = "e", type = "u16" },
+            ]
+        "#;
+        let mut data = vec![0x80, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xfd];
+        data.extend_from_slice(&i64::MIN.to_le_bytes());
+        data.extend_from_slice(&[0xff, 0xff]);
+        assert_eq!(
+            values(template, &data),
+            [
+                "a = -128",
+                "b = -2",
+                "c = -3",
+                &format!("d = {}", i64::MIN),
+                "e = 65535",
+            ]
+        );
+    }
+
+    #[test]
+    fn recursive_structs_are_an_error() {
+        let template = r#"
+            fields = [{ name = "list", type = "node" }]
+
+            [structs]
+            node = [{ name = "next", type = "node" }]
+        "#;
+        let error = decode(template, &[]).err().expect("too deep");
+        assert_eq!(
+            error,
+            format!("structs nested deeper than {MAX_DEPTH} levels at field 'next'")
+        );
+    }
+
+    #[test]
+    fn too_many_fields_are_an_error() {
+        let template = format!(
+            r#"fields = [{{ name = "empty", type = "bytes", length = 0, count = {} }}]"#,
+            MAX_NODES + 1
+        );
+        let error = decode(&template, &[]).err().expect("too many fields");
+        assert_eq!(error, format!("more than {MAX_NODES} fields decoded"));
+    }
+
+    #[test]
+    fn missing_bytes_are_an_error() {
+        let template = r#"
+            fields = [
+                { name = "a", type = "u8" },
+                { name = "b", type = "u32" },
+            ]
+        "#;
+        let schema = schema::parse(template).expect("valid template");
+        let error = Decoder::new(&schema, &[1, 2, 3], 0x10)
+            .fields(&schema.fields, 0)
+            .err()
+            .expect("too short");
+        assert_eq!(error, "field 'b' at offset 0x11 needs 4 bytes, only 2 left");
+    }
+}
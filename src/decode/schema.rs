@@ -0,0 +1,415 @@
+use crate::config::color_choice::Shade;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use owo_colors::DynColors;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/// A count or length: a number, or the name of an earlier integer field holding it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Size {
+    Fixed(usize),
+    Field(String),
+}
+
+/// A field as written in the template.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct FieldFile {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    endian: Option<Endian>,
+    count: Option<Size>,
+    length: Option<Size>,
+    length_prefix: Option<String>,
+    #[serde(rename = "enum")]
+    enumeration: Option<String>,
+    color: Option<String>,
+}
+
+/// Contents of a template file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaFile {
+    #[serde(default)]
+    endian: Endian,
+    fields: Vec<FieldFile>,
+    #[serde(default)]
+    structs: BTreeMap<String, Vec<FieldFile>>,
+    #[serde(default)]
+    enums: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Integer {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+}
+
+impl Integer {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "u8" => Integer::U8,
+            "u16" => Integer::U16,
+            "u32" => Integer::U32,
+            "u64" => Integer::U64,
+            "i8" => Integer::I8,
+            "i16" => Integer::I16,
+            "i32" => Integer::I32,
+            "i64" => Integer::I64,
+            _ => return None,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Integer::U8 | Integer::I8 => 1,
+            Integer::U16 | Integer::I16 => 2,
+            Integer::U32 | Integer::I32 => 4,
+            Integer::U64 | Integer::I64 => 8,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            Integer::I8 | Integer::I16 | Integer::I32 | Integer::I64
+        )
+    }
+}
+
+/// How the length of a byte array or string is given.
+#[derive(Debug, Clone)]
+pub enum Length {
+    Size(Size),
+    /// An integer of this type right before the data.
+    Prefix(Integer),
+}
+
+#[derive(Debug, Clone)]
+pub enum Kind {
+    Integer {
+        integer: Integer,
+        /// The name of the enum naming the values.
+        enumeration: Option<String>,
+    },
+    F32,
+    F64,
+    Bytes(Length),
+    String(Length),
+    Struct(String),
+}
+
+/// A field of a struct.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    /// The type as written in the template.
+    pub type_name: String,
+    pub kind: Kind,
+    pub endian: Endian,
+    /// Makes the field an array with this many elements.
+    pub count: Option<Size>,
+    pub color: Option<DynColors>,
+}
+
+/// A template: the fields decoded from the start position, the structs they refer to and the
+/// names of enum values.
+#[derive(Debug)]
+pub struct Schema {
+    pub fields: Vec<Field>,
+    pub structs: HashMap<String, Vec<Field>>,
+    pub enums: HashMap<String, HashMap<i128, String>>,
+}
+
+/// Parses a number like `16`, `-1` or `0x10`.
+fn parse_number(number: &str) -> Option<i128> {
+    let number = number.trim();
+    let (negative, digits) = match number.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, number),
+    };
+    let value = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+fn field(
+    file: &FieldFile,
+    endian: Endian,
+    structs: &BTreeMap<String, Vec<FieldFile>>,
+    enums: &BTreeMap<String, BTreeMap<String, String>>,
+) -> Result<Field, String> {
+    let length = || -> Result<Length, String> {
+        match (&file.length, &file.length_prefix) {
+            (Some(length), None) => Ok(Length::Size(length.clone())),
+            (None, Some(prefix)) => Integer::parse(prefix)
+                .map(Length::Prefix)
+                .ok_or_else(|| format!("the length prefix '{prefix}' is not an integer type")),
+            _ => Err(format!(
+                "a {} needs either a length or a length-prefix",
+                file.kind
+            )),
+        }
+    };
+
+    let kind = if let Some(integer) = Integer::parse(&file.kind) {
+        if let Some(enumeration) = &file.enumeration
+            && !enums.contains_key(enumeration)
+        {
+            return Err(format!("unknown enum '{enumeration}'"));
+        }
+        Kind::Integer {
+            integer,
+            enumeration: file.enumeration.clone(),
+        }
+    } else {
+        if file.enumeration.is_some() {
+            return Err("only integers can have an enum".to_string());
+        }
+        match file.kind.as_str() {
+            "f32" => Kind::F32,
+            "f64" => Kind::F64,
+            "bytes" => Kind::Bytes(length()?),
+            "string" => Kind::String(length()?),
+            name if structs.contains_key(name) => Kind::Struct(name.to_string()),
+            name => return Err(format!("unknown type '{name}'")),
+        }
+    };
+    if !matches!(kind, Kind::Bytes(_) | Kind::String(_))
+        && (file.length.is_some() || file.length_prefix.is_some())
+    {
+        return Err("only bytes and strings have a length".to_string());
+    }
+
+    let color = match &file.color {
+        Some(color) => Some(
+            color
+                .parse::<Shade>()?
+                .color
+                .ok_or_else(|| format!("'{color}' is not a color"))?,
+        ),
+        None => None,
+    };
+
+    Ok(Field {
+        name: file.name.clone(),
+        type_name: file.kind.clone(),
+        kind,
+        endian: file.endian.unwrap_or(endian),
+        count: file.count.clone(),
+        color,
+    })
+}
+
+/// Loads and checks a template file.
+pub fn load(path: &Path) -> io::Result<Schema> {
+    let contents = fs::read_to_string(path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Could not read template file {}: {err}", path.display()),
+        )
+    })?;
+    parse(&contents).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid template file {}: {err}", path.display()),
+        )
+    })
+}
+
+/// Parses and checks the contents of a template file.
+pub fn parse(contents: &str) -> Result<Schema, String> {
+    let file: SchemaFile = toml::from_str(contents).map_err(|err| err.to_string())?;
+
+    let fields = |struct_name: &str, fields: &[FieldFile]| {
+        fields
+            .iter()
+            .map(|field_file| {
+                field(field_file, file.endian, &file.structs, &file.enums)
+                    .map_err(|err| format!("field '{struct_name}{}': {err}", field_file.name))
+            })
+            .collect::<Result<Vec<Field>, String>>()
+    };
+
+    let mut structs = HashMap::new();
+    for (name, struct_fields) in &file.structs {
+        structs.insert(name.clone(), fields(&format!("{name}."), struct_fields)?);
+    }
+
+    let mut enums = HashMap::new();
+    for (name, values) in &file.enums {
+        let mut names = HashMap::new();
+        for (value, value_name) in values {
+            let value = parse_number(value)
+                .ok_or_else(|| format!("enum '{name}': invalid value '{value}'"))?;
+            names.insert(value, value_name.clone());
+        }
+        enums.insert(name.clone(), names);
+    }
+
+    Ok(Schema {
+        fields: fields("", &file.fields)?,
+        structs,
+        enums,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(template: &str) -> String {
+        parse(template).expect_err("invalid template")
+    }
+
+    #[test]
+    fn byte_order_of_the_template_and_the_fields() {
+        let schema = parse(
+            r#"
+            endian = "big"
+            fields = [
+                { name = "a", type = "u16" },
+                { name = "b", type = "u16", endian = "little" },
+            ]
+            "#,
+        )
+        .expect("valid template");
+        let endians: Vec<Endian> = schema.fields.iter().map(|field| field.endian).collect();
+        assert_eq!(endians, [Endian::Big, Endian::Little]);
+
+        let schema = parse(r#"fields = [{ name = "a", type = "u16" }]"#).expect("valid template");
+        assert_eq!(schema.fields[0].endian, Endian::Little);
+    }
+
+    #[test]
+    fn kinds() {
+        let schema = parse(
+            r#"
+            fields = [
+                { name = "a", type = "i32", enum = "e" },
+                { name = "b", type = "string", length-prefix = "u8" },
+                { name = "c", type = "bytes", length = "a", count = 2 },
+                { name = "d", type = "s" },
+            ]
+
+            [structs]
+            s = [{ name = "x", type = "f64", color = "red" }]
+
+            [enums.e]
+            0 = "zero"
+            "#,
+        )
+        .expect("valid template");
+
+        assert!(matches!(
+            &schema.fields[0].kind,
+            Kind::Integer { integer: Integer::I32, enumeration: Some(name) } if name == "e"
+        ));
+        assert!(matches!(
+            schema.fields[1].kind,
+            Kind::String(Length::Prefix(Integer::U8))
+        ));
+        assert!(matches!(
+            &schema.fields[2].kind,
+            Kind::Bytes(Length::Size(Size::Field(name))) if name == "a"
+        ));
+        assert!(matches!(schema.fields[2].count, Some(Size::Fixed(2))));
+        assert!(matches!(&schema.fields[3].kind, Kind::Struct(name) if name == "s"));
+        assert!(matches!(schema.structs["s"][0].kind, Kind::F64));
+        assert!(schema.structs["s"][0].color.is_some());
+        assert_eq!(schema.enums["e"][&0], "zero");
+    }
+
+    #[test]
+    fn invalid_fields() {
+        assert_eq!(
+            error(r#"fields = [{ name = "a", type = "u24" }]"#),
+            "field 'a': unknown type 'u24'"
+        );
+        assert_eq!(
+            error(r#"fields = [{ name = "a", type = "string" }]"#),
+            "field 'a': a string needs either a length or a length-prefix"
+        );
+        assert_eq!(
+            error(r#"fields = [{ name = "a", type = "bytes", length = 1, length-prefix = "u8" }]"#),
+            "field 'a': a bytes needs either a length or a length-prefix"
+        );
+        assert_eq!(
+            error(r#"fields = [{ name = "a", type = "bytes", length-prefix = "f32" }]"#),
+            "field 'a': the length prefix 'f32' is not an integer type"
+        );
+        assert_eq!(
+            error(r#"fields = [{ name = "a", type = "u8", length = 2 }]"#),
+            "field 'a': only bytes and strings have a length"
+        );
+        assert_eq!(
+            error(r#"fields = [{ name = "a", type = "u8", color = "bold" }]"#),
+            "field 'a': 'bold' is not a color"
+        );
+        assert!(error(r#"fields = [{ name = "a", type = "u8", size = 2 }]"#).contains("size"));
+    }
+
+    #[test]
+    fn invalid_enums() {
+        assert_eq!(
+            error(r#"fields = [{ name = "a", type = "u8", enum = "e" }]"#),
+            "field 'a': unknown enum 'e'"
+        );
+        assert_eq!(
+            error(
+                r#"
+                fields = [{ name = "a", type = "f32", enum = "e" }]
+                [enums.e]
+                1 = "one"
+                "#
+            ),
+            "field 'a': only integers can have an enum"
+        );
+        assert_eq!(
+            error(
+                r#"
+                fields = []
+                [enums.e]
+                one = "one"
+                "#
+            ),
+            "enum 'e': invalid value 'one'"
+        );
+    }
+
+    #[test]
+    fn errors_in_structs_name_the_struct() {
+        assert_eq!(
+            error(
+                r#"
+                fields = [{ name = "h", type = "header" }]
+                [structs]
+                header = [{ name = "size", type = "usize" }]
+                "#
+            ),
+            "field 'header.size': unknown type 'usize'"
+        );
+    }
+}
@@ -0,0 +1,684 @@
+use crate::config::Autoskip;
+use crate::config::Config;
+use crate::config::color_choice::{ColorDepth, Shade, colorize};
+use crate::config::highlight::{Highlight, Target};
+use std::io::{self, Read, Write};
+
+const SPACE: u8 = 0x20;
+const NUL: u8 = 0x00;
+/// Match offsets of a pattern listed in the legend, the rest is only counted.
+const LEGEND_OFFSETS: usize = 8;
+
+/// The escape sequences `colorize` puts before and after a text, empty if color is disabled.
+fn color_escapes(shade: Shade, color_depth: ColorDepth) -> (Vec<u8>, Vec<u8>) {
+    let colored = colorize("\0", shade, color_depth);
+    let (prefix, suffix) = colored.split_once('\0').expect("marker is kept");
+    (prefix.as_bytes().to_vec(), suffix.as_bytes().to_vec())
+}
+
+/// Everything a dump line is made of, rendered once for every octet value and both row colors,
+/// so rendering a line neither formats nor allocates. Tables are indexed by `row_flag as usize`.
+struct RenderTables {
+    octets_per_line: usize,
+    /// Characters per octet, wider than the format with values to fit below the octets.
+    octet_width: usize,
+    octets: [Vec<Vec<u8>>; 2],
+    text: [Vec<Vec<u8>>; 2],
+    plain: Vec<Vec<u8>>,
+    panel: [(Vec<u8>, Vec<u8>); 2],
+    /// The escapes around a highlighted octet or glyph, one pair per highlight.
+    highlights: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The escapes around the values below the octets.
+    values: [(Vec<u8>, Vec<u8>); 2],
+}
+
+impl RenderTables {
+    fn new(config: &Config) -> Self {
+        // a value spans the columns of its octets and the spaces between them
+        let octet_width = config
+            .value_types
+            .iter()
+            .map(|value_type| {
+                let size = value_type.size();
+                (value_type.width() - (size - 1)).div_ceil(size)
+            })
+            .fold(config.format.width(), usize::max);
+        let row = |row_flag: bool| {
+            let colors = &config.colors;
+            let mut octets = Vec::with_capacity(256);
+            let mut text = Vec::with_capacity(256);
+
+            for byte in 0..=u8::MAX {
+                let value = format!(
+                    "{:>octet_width$}",
+                    config.format.value(byte, config.uppercase)
+                );
+                let control_picture = char::from_u32(0x2400 + byte as u32)
+                    .unwrap_or('�')
+                    .to_string();
+                let (octet_color, glyph, text_color) = if byte.is_ascii_graphic() {
+                    (
+                        colors.dump_text,
+                        (byte as char).to_string(),
+                        colors.panel_text,
+                    )
+                } else if byte == NUL {
+                    (colors.nul_char, control_picture, colors.nul_char)
+                } else if byte.is_ascii_control() || byte == SPACE {
+                    (colors.control_char, control_picture, colors.control_char)
+                } else {
+                    (
+                        colors.undefined_char,
+                        ".".to_string(),
+                        colors.undefined_char,
+                    )
+                };
+
+                // the last matching rule overrides the byte class
+                let (octet_color, glyph, text_color) = match config
+                    .color_rules
+                    .iter()
+                    .rev()
+                    .find(|rule| rule.matches(byte))
+                {
+                    Some(rule) => {
+                        let (octet_color, text_color) = match rule.shade {
+                            Some(shade) => (shade, shade),
+                            None => (octet_color.get(row_flag), text_color.get(row_flag)),
+                        };
+                        let glyph = rule.glyph.map(String::from).unwrap_or(glyph);
+                        (octet_color, glyph, text_color)
+                    }
+                    None => (octet_color.get(row_flag), glyph, text_color.get(row_flag)),
+                };
+
+                octets.push(colorize(&value, octet_color, config.color_depth).into_bytes());
+                text.push(colorize(&glyph, text_color, config.color_depth).into_bytes());
+            }
+
+            (octets, text)
+        };
+        let (normal_octets, normal_text) = row(false);
+        let (alternate_octets, alternate_text) = row(true);
+
+        Self {
+            octets_per_line: octets_per_line(config.cols),
+            octet_width,
+            octets: [normal_octets, alternate_octets],
+            text: [normal_text, alternate_text],
+            plain: (0..=u8::MAX)
+                .map(|byte| config.format.value(byte, config.uppercase).into_bytes())
+                .collect(),
+            panel: [
+                color_escapes(config.colors.panel_text.get(false), config.color_depth),
+                color_escapes(config.colors.panel_text.get(true), config.color_depth),
+            ],
+            highlights: config
+                .highlights
+                .iter()
+                .enumerate()
+                .map(|(i, highlight)| {
+                    color_escapes(
+                        Shade::plain().on(highlight.background(i)),
+                        config.color_depth,
+                    )
+                })
+                .collect(),
+            values: [
+                color_escapes(config.colors.dump_text.get(false), config.color_depth),
+                color_escapes(config.colors.dump_text.get(true), config.color_depth),
+            ],
+        }
+    }
+}
+
+/// Octets on a line of the dump layout for `--cols`, 0 giving long lines of 32 octets.
+pub fn octets_per_line(cols: u16) -> usize {
+    if cols > 0 { cols as usize } else { 32 }
+}
+
+/// Reads until `buffer` is full or EOF is reached, so short reads from pipes don't break lines.
+pub fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break, // EOF
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(filled)
+}
+
+/// A reader that can look at the bytes ahead without consuming them.
+struct Lookahead<R> {
+    reader: R,
+    ahead: Vec<u8>,
+}
+
+impl<R: Read> Lookahead<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            ahead: Vec::new(),
+        }
+    }
+
+    /// Returns up to `len` of the next bytes, fewer only at EOF.
+    fn peek(&mut self, len: usize) -> io::Result<&[u8]> {
+        if self.ahead.len() < len {
+            let filled = self.ahead.len();
+            self.ahead.resize(len, 0);
+            let read = read_full(&mut self.reader, &mut self.ahead[filled..])?;
+            self.ahead.truncate(filled + read);
+        }
+        Ok(&self.ahead[..len.min(self.ahead.len())])
+    }
+}
+
+impl<R: Read> Read for Lookahead<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.ahead.is_empty() {
+            return self.reader.read(buffer);
+        }
+        let len = buffer.len().min(self.ahead.len());
+        buffer[..len].copy_from_slice(&self.ahead[..len]);
+        self.ahead.drain(..len);
+        Ok(len)
+    }
+}
+
+/// The highlights on a line: the highlight of each byte and the regions starting on the line.
+#[derive(Default)]
+struct LineMarks {
+    bytes: Vec<Option<usize>>,
+    labels: Vec<usize>,
+}
+
+impl LineMarks {
+    fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.bytes.iter().all(Option::is_none)
+    }
+}
+
+/// Finds the highlighted bytes of each line. Patterns are searched in a window reaching back and
+/// ahead by the length of the longest pattern, so a match spanning lines is marked on all of them.
+struct Marker<'a> {
+    highlights: &'a [Highlight],
+    /// How far the window reaches back and ahead.
+    reach: usize,
+    /// The last bytes before the current line.
+    history: Vec<u8>,
+    window: Vec<u8>,
+    /// The highlights of the current line.
+    marks: LineMarks,
+    /// Bytes in a range or matches of a pattern seen so far, per highlight.
+    counts: Vec<usize>,
+    /// The first offsets a pattern matched at, per highlight.
+    offsets: Vec<Vec<usize>>,
+}
+
+impl<'a> Marker<'a> {
+    fn new(highlights: &'a [Highlight]) -> Self {
+        let reach = highlights
+            .iter()
+            .map(|highlight| match &highlight.target {
+                Target::Pattern(pattern) => pattern.len() - 1,
+                Target::Range { .. } | Target::Region { .. } => 0,
+            })
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            highlights,
+            reach,
+            history: Vec::new(),
+            window: Vec::new(),
+            marks: LineMarks::default(),
+            counts: vec![0; highlights.len()],
+            offsets: vec![Vec::new(); highlights.len()],
+        }
+    }
+
+    /// Marks the bytes of the line at `offset`, `ahead` being the bytes that follow it. Later
+    /// highlights win where they overlap.
+    fn mark(&mut self, offset: usize, bytes: &[u8], ahead: &[u8]) -> &LineMarks {
+        self.marks.bytes.clear();
+        self.marks.labels.clear();
+        if self.highlights.is_empty() {
+            return &self.marks;
+        }
+        self.marks.bytes.resize(bytes.len(), None);
+        let line_end = offset + bytes.len();
+
+        self.window.clear();
+        self.window.extend_from_slice(&self.history);
+        self.window.extend_from_slice(bytes);
+        self.window.extend_from_slice(ahead);
+        let window_start = offset - self.history.len();
+
+        for (index, highlight) in self.highlights.iter().enumerate() {
+            match &highlight.target {
+                Target::Range { start, end } | Target::Region { start, end } => {
+                    if matches!(highlight.target, Target::Region { .. })
+                        && (offset..line_end).contains(start)
+                    {
+                        self.marks.labels.push(index);
+                    }
+                    let from = (*start).max(offset);
+                    let to = (*end).min(line_end);
+                    for mark in self
+                        .marks
+                        .bytes
+                        .iter_mut()
+                        .take(to.saturating_sub(offset))
+                        .skip(from - offset)
+                    {
+                        *mark = Some(index);
+                    }
+                    self.counts[index] += to.saturating_sub(from);
+                }
+                Target::Pattern(pattern) => {
+                    for (i, candidate) in self.window.windows(pattern.len()).enumerate() {
+                        let start = window_start + i;
+                        let end = start + pattern.len();
+                        if end <= offset || start >= line_end || candidate != pattern.as_slice() {
+                            continue;
+                        }
+                        // a match is counted on the line it starts on
+                        if start >= offset {
+                            self.counts[index] += 1;
+                            if self.offsets[index].len() < LEGEND_OFFSETS {
+                                self.offsets[index].push(start);
+                            }
+                        }
+                        for mark in &mut self.marks.bytes
+                            [start.max(offset) - offset..end.min(line_end) - offset]
+                        {
+                            *mark = Some(index);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.history.extend_from_slice(bytes);
+        let excess = self.history.len().saturating_sub(self.reach);
+        self.history.drain(..excess);
+
+        &self.marks
+    }
+
+    /// Writes a line per highlight in its color: the bytes in a range or where a pattern matched.
+    /// Regions are left out, their labels are on the lines already. Legend lines start with `#`,
+    /// so `reverse` skips them.
+    fn write_legend<W: Write>(
+        &self,
+        out: &mut W,
+        tables: &RenderTables,
+        config: &Config,
+    ) -> io::Result<()> {
+        let radix = if config.decimal_offset { 10 } else { 16 };
+        let mut line = Vec::new();
+
+        for (index, highlight) in self.highlights.iter().enumerate() {
+            if matches!(highlight.target, Target::Region { .. }) {
+                continue;
+            }
+            let (prefix, suffix) = &tables.highlights[index];
+            line.clear();
+            line.extend_from_slice(b"# ");
+            line.extend_from_slice(prefix);
+            line.extend_from_slice(highlight.label.as_bytes());
+            line.extend_from_slice(suffix);
+
+            let count = self.counts[index];
+            match highlight.target {
+                Target::Range { .. } | Target::Region { .. } => {
+                    line.extend_from_slice(format!("  {count} bytes").as_bytes());
+                }
+                Target::Pattern(_) if count == 0 => line.extend_from_slice(b"  no matches"),
+                Target::Pattern(_) => {
+                    let noun = if count == 1 { "match" } else { "matches" };
+                    line.extend_from_slice(format!("  {count} {noun} at ").as_bytes());
+                    for (i, &offset) in self.offsets[index].iter().enumerate() {
+                        if i != 0 {
+                            line.extend_from_slice(b", ");
+                        }
+                        push_offset(&mut line, offset, radix, config.uppercase);
+                    }
+                    if count > self.offsets[index].len() {
+                        line.extend_from_slice(b", ...");
+                    }
+                }
+            }
+            line.push(b'\n');
+            out.write_all(&line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Appends `offset` with at least 8 digits, like `{offset:08x}` but without the formatting
+/// machinery, which dominates the time spent on a line otherwise.
+fn push_offset(line: &mut Vec<u8>, mut offset: usize, radix: usize, uppercase: bool) {
+    let digits_of_radix = if uppercase {
+        b"0123456789ABCDEF"
+    } else {
+        b"0123456789abcdef"
+    };
+    let mut digits = [b'0'; 24];
+    let mut start = digits.len();
+
+    while offset > 0 || digits.len() - start < 8 {
+        start -= 1;
+        digits[start] = digits_of_radix[offset % radix];
+        offset /= radix;
+    }
+    line.extend_from_slice(&digits[start..]);
+}
+
+/// Renders a single line of the dump layout into `line`: offset, octets and text, followed by a
+/// row of values for every `--type`.
+fn render_line(
+    line: &mut Vec<u8>,
+    bytes: &[u8],
+    offset: usize,
+    row_flag: bool,
+    marks: &LineMarks,
+    tables: &RenderTables,
+    config: &Config,
+) {
+    let row = usize::from(row_flag);
+    let octets_per_line = tables.octets_per_line;
+    // highlighted octets and glyphs get a background around their colors
+    let push = |line: &mut Vec<u8>, rendered: &[u8], i: usize| match marks.bytes.get(i) {
+        Some(Some(highlight)) => {
+            let (prefix, suffix) = &tables.highlights[*highlight];
+            line.extend_from_slice(prefix);
+            line.extend_from_slice(rendered);
+            line.extend_from_slice(suffix);
+        }
+        _ => line.extend_from_slice(rendered),
+    };
+    let grouping = config.grouping as usize;
+    line.clear();
+
+    let mut offset_width = 0;
+    if config.show_offset {
+        let (prefix, suffix) = &tables.panel[row];
+        line.extend_from_slice(prefix);
+        let radix = if config.decimal_offset { 10 } else { 16 };
+        let start = line.len();
+        push_offset(line, offset, radix, config.uppercase);
+        offset_width = line.len() - start;
+        line.extend_from_slice(b": ");
+        line.extend_from_slice(suffix);
+    }
+
+    let group_len = if grouping > 0 {
+        grouping
+    } else {
+        octets_per_line
+    };
+    for (i, group) in bytes.chunks(group_len).enumerate() {
+        let first = i * group_len;
+        if i != 0 {
+            // Extra space to separate groups, highlighted within a highlight
+            match (marks.bytes.get(first - 1), marks.bytes.get(first)) {
+                (Some(Some(before)), Some(Some(after))) if before == after => {
+                    push(line, b" ", first)
+                }
+                _ => line.push(b' '),
+            }
+        }
+        // little-endian groups show the last octet first, a short last group is right-aligned
+        // like xxd -e does it, so its octets stay in the columns of their significance
+        if config.little_endian {
+            line.resize(
+                line.len() + (group_len - group.len()) * tables.octet_width,
+                b' ',
+            );
+            for (j, &byte) in group.iter().enumerate().rev() {
+                push(line, &tables.octets[row][byte as usize], first + j);
+            }
+        } else {
+            for (j, &byte) in group.iter().enumerate() {
+                push(line, &tables.octets[row][byte as usize], first + j);
+            }
+        }
+    }
+    line.push(b' ');
+
+    // Pad for short lines, a little-endian group is padded already
+    let padded = if config.little_endian {
+        bytes.len().next_multiple_of(group_len)
+    } else {
+        bytes.len()
+    };
+    for i in padded..octets_per_line {
+        line.resize(line.len() + tables.octet_width, b' ');
+        if grouping > 0 && i % grouping == 0 {
+            line.push(b' ');
+        }
+    }
+
+    if config.show_text {
+        for (i, &byte) in bytes.iter().enumerate() {
+            push(line, &tables.text[row][byte as usize], i);
+        }
+    }
+
+    // the labels of the regions starting on this line, in a column of their own
+    for (i, &region) in marks.labels.iter().enumerate() {
+        if i == 0 {
+            if config.show_text {
+                line.resize(line.len() + octets_per_line - bytes.len(), b' ');
+            }
+            line.extend_from_slice(b"  ");
+        } else {
+            line.extend_from_slice(b", ");
+        }
+        let (prefix, suffix) = &tables.highlights[region];
+        line.extend_from_slice(prefix);
+        line.extend_from_slice(config.highlights[region].label.as_bytes());
+        line.extend_from_slice(suffix);
+    }
+    line.push(b'\n');
+
+    // the values right-aligned below their octets, labeled with their type in the offset column
+    for value_type in &config.value_types {
+        if config.show_offset {
+            let (prefix, suffix) = &tables.panel[row];
+            line.extend_from_slice(prefix);
+            line.extend_from_slice(
+                format!("{:>offset_width$}  ", value_type.to_string()).as_bytes(),
+            );
+            line.extend_from_slice(suffix);
+        }
+        let size = value_type.size();
+        let span = size * tables.octet_width + size - 1;
+        let (prefix, suffix) = &tables.values[row];
+        line.extend_from_slice(prefix);
+        for (i, octets) in bytes.chunks(size).enumerate() {
+            if i != 0 {
+                line.push(b' ');
+            }
+            // a partial value at the end is padded with zeros, like od -t does it
+            let mut value = [0u8; 8];
+            value[..octets.len()].copy_from_slice(octets);
+            line.extend_from_slice(format!("{:>span$}", value_type.value(&value)).as_bytes());
+        }
+        line.extend_from_slice(suffix);
+        line.push(b'\n');
+    }
+}
+
+pub fn dump<R: Read, W: Write>(reader: R, out: &mut W, config: &Config) -> io::Result<()> {
+    let tables = RenderTables::new(config);
+    let octets_per_line = tables.octets_per_line;
+    let mut reader = Lookahead::new(reader);
+    let mut marker = Marker::new(&config.highlights);
+    let mut buffer = vec![0u8; octets_per_line]; // Read in chunks of octets_per_line bytes
+    let mut line = Vec::new();
+    let mut offset = config.offset;
+    let mut total_read: usize = 0;
+    // with autoskip: the last line written and the offset of the last line replaced by `*`
+    let mut previous = Vec::new();
+    let mut skipped: Option<usize> = None;
+
+    let mut row_flag = true;
+    loop {
+        let to_read: usize = std::cmp::min(octets_per_line, config.length - total_read);
+        let bytes_read = read_full(&mut reader, &mut buffer[..to_read])?;
+        if bytes_read == 0 {
+            // EOF reached
+            break;
+        }
+
+        let reach = marker.reach.min(config.length - total_read - bytes_read);
+        let ahead = if reach > 0 { reader.peek(reach)? } else { &[] };
+        let marks = marker.mark(offset, &buffer[..bytes_read], ahead);
+
+        if config.plain {
+            line.clear();
+            for &byte in &buffer[..bytes_read] {
+                line.extend_from_slice(&tables.plain[byte as usize]);
+            }
+            if config.cols > 0 {
+                line.push(b'\n');
+            }
+            out.write_all(&line)?;
+        } else if let Some(autoskip) = config.autoskip
+            && bytes_read == octets_per_line
+            && previous == buffer
+            && marks.is_empty()
+            && (autoskip == Autoskip::Identical || buffer.iter().all(|&byte| byte == NUL))
+        {
+            if skipped.is_none() {
+                let (prefix, suffix) = &tables.panel[usize::from(row_flag)];
+                out.write_all(prefix)?;
+                out.write_all(b"*")?;
+                out.write_all(suffix)?;
+                out.write_all(b"\n")?;
+            } else {
+                // only the `*` line counts as a row
+                row_flag = !row_flag;
+            }
+            skipped = Some(offset);
+        } else {
+            render_line(
+                &mut line,
+                &buffer[..bytes_read],
+                offset,
+                row_flag,
+                marks,
+                &tables,
+                config,
+            );
+            out.write_all(&line)?;
+            if config.autoskip.is_some() {
+                previous.clone_from(&buffer);
+            }
+            skipped = None;
+        }
+
+        total_read += bytes_read;
+        offset += bytes_read;
+        row_flag = !row_flag;
+    }
+
+    // always write the last line so the length of a skipped run is known
+    if let Some(offset) = skipped {
+        render_line(
+            &mut line,
+            &previous,
+            offset,
+            row_flag,
+            &LineMarks::default(),
+            &tables,
+            config,
+        );
+        out.write_all(&line)?;
+    }
+
+    if !config.plain {
+        marker.write_legend(out, &tables, config)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Marks `data` in lines of `cols` octets like `dump` does, returning the marks of each line.
+    fn mark_lines(marker: &mut Marker, data: &[u8], cols: usize) -> Vec<Vec<Option<usize>>> {
+        data.chunks(cols)
+            .enumerate()
+            .map(|(i, bytes)| {
+                let offset = i * cols;
+                let rest = &data[offset + bytes.len()..];
+                let ahead = &rest[..rest.len().min(marker.reach)];
+                marker.mark(offset, bytes, ahead).bytes.clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pattern_spanning_a_line_break() {
+        let highlights = [Highlight::pattern("0xbbcc").expect("valid pattern")];
+        let mut marker = Marker::new(&highlights);
+
+        let marks = mark_lines(&mut marker, &[0xaa, 0xbb, 0xcc, 0xdd], 2);
+        assert_eq!(marks, [[None, Some(0)], [Some(0), None]]);
+        // counted once, on the line it starts on
+        assert_eq!(marker.counts, [1]);
+        assert_eq!(marker.offsets, [[1]]);
+    }
+
+    #[test]
+    fn pattern_longer_than_a_line() {
+        let highlights = [Highlight::pattern("bcdef").expect("valid pattern")];
+        let mut marker = Marker::new(&highlights);
+
+        let marks = mark_lines(&mut marker, b"abcdefg", 2);
+        let marked: Vec<bool> = marks.concat().iter().map(Option::is_some).collect();
+        assert_eq!(marked, [false, true, true, true, true, true, false]);
+        assert_eq!(marker.counts, [1]);
+    }
+
+    #[test]
+    fn legend_counts() {
+        let highlights = [
+            Highlight::range("1..6").expect("valid range"),
+            Highlight::pattern("a").expect("valid pattern"),
+            Highlight::pattern("z").expect("valid pattern"),
+        ];
+        let mut marker = Marker::new(&highlights);
+
+        let data = b"a".repeat(LEGEND_OFFSETS + 2);
+        mark_lines(&mut marker, &data, 4);
+        // bytes of the range, but every match of the pattern
+        assert_eq!(marker.counts, [5, LEGEND_OFFSETS + 2, 0]);
+        assert_eq!(marker.offsets[1], (0..LEGEND_OFFSETS).collect::<Vec<_>>());
+        assert!(marker.offsets[2].is_empty());
+    }
+
+    #[test]
+    fn later_highlights_win() {
+        let highlights = [
+            Highlight::pattern("abc").expect("valid pattern"),
+            Highlight::range("1").expect("valid range"),
+        ];
+        let mut marker = Marker::new(&highlights);
+
+        let marks = mark_lines(&mut marker, b"abc", 4);
+        assert_eq!(marks, [[Some(0), Some(1), Some(0)]]);
+    }
+}
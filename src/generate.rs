@@ -0,0 +1,642 @@
+use crate::bytes;
+use crate::config::{AlignStyle, Config, ElementType, Language, Padding};
+use crate::dump;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Keywords of the languages, names that are one get a `_` appended.
+const C_KEYWORDS: &str = "\
+    auto bool break case char const constexpr continue default do double else enum extern \
+    false float for goto if inline int long nullptr register restrict return short signed \
+    sizeof static struct switch true typedef typeof union unsigned void volatile while";
+const CPP_KEYWORDS: &str = "\
+    alignas alignof and asm catch class concept const_cast consteval constinit co_await \
+    co_return co_yield decltype delete dynamic_cast explicit export friend mutable namespace \
+    new noexcept not operator or private protected public reinterpret_cast requires \
+    static_assert static_cast template this thread_local throw try typeid typename using \
+    virtual wchar_t xor";
+const PYTHON_KEYWORDS: &str = "\
+    False None True and as assert async await break class continue def del elif else except \
+    finally for from global if import in is lambda nonlocal not or pass raise return try while \
+    with yield";
+const GO_KEYWORDS: &str = "\
+    break case chan const continue default defer else fallthrough for func go goto if import \
+    interface map package range return select struct switch type var";
+/// Zig also forbids shadowing its primitive types and values.
+const ZIG_KEYWORDS: &str = "\
+    addrspace align allowzero and anyframe anytype asm async await break callconv catch \
+    comptime const continue defer else enum errdefer error export extern fn for if inline \
+    linksection noalias noinline nosuspend opaque or orelse packed pub resume return struct \
+    suspend switch test threadlocal try union unreachable usingnamespace var volatile while \
+    anyerror anyopaque bool c_char c_int c_long c_longdouble c_longlong c_short c_uint c_ulong \
+    c_ulonglong c_ushort comptime_float comptime_int f16 f32 f64 f80 f128 false isize noreturn \
+    null true type undefined usize void";
+const NIM_KEYWORDS: &str = "\
+    addr and as asm bind block break case cast concept const continue converter defer discard \
+    distinct div do elif else end enum except export finally for from func if import in \
+    include interface is isnot iterator let macro method mixin mod nil not notin object of or \
+    out proc ptr raise ref return shl shr static template try tuple type using var when while \
+    xor yield";
+const D_KEYWORDS: &str = "\
+    abstract alias align asm assert auto body bool break byte case cast catch cdouble cent \
+    cfloat char class const continue creal dchar debug default delegate delete deprecated do \
+    double else enum export extern false final finally float for foreach foreach_reverse \
+    function goto idouble if ifloat immutable import in inout int interface invariant ireal is \
+    lazy long macro mixin module new nothrow null out override package pragma private \
+    protected public pure real ref return scope shared short static struct super switch \
+    synchronized template this throw true try typeid typeof ubyte ucent uint ulong union \
+    unittest ushort version void wchar while with";
+
+/// Whether names in the language are camel case, like `logoPngLen`.
+fn camel_case(language: Language) -> bool {
+    matches!(language, Language::Go | Language::Nim | Language::D)
+}
+
+/// Turns `name` into a legal identifier of the language: other characters than ASCII letters,
+/// digits and `_` become `_`, a leading digit gets a `_` before it and keywords one after them.
+/// Rust constants are upper case, with a `_` where the case of a camel case name changes. Go, Nim
+/// and D names are camel case instead, `-C` makes them start upper case, which exports a Go name.
+/// Nim has no names starting with `_` or ending with one, it gets an `x` before a leading digit
+/// and keywords are quoted in backticks.
+fn identifier(name: &str, config: &Config) -> io::Result<String> {
+    let mut identifier = String::with_capacity(name.len() + 1);
+    let mut previous_lower = false;
+    for ch in name.chars() {
+        if config.language == Language::Rust {
+            if ch.is_ascii_uppercase() && previous_lower {
+                identifier.push('_');
+            }
+            previous_lower = ch.is_ascii_lowercase() || ch.is_ascii_digit();
+        }
+        identifier.push(if ch.is_ascii_alphanumeric() { ch } else { '_' });
+    }
+    if identifier.chars().all(|ch| ch == '_') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{name}' is no valid variable name, set one with --name"),
+        ));
+    }
+
+    if camel_case(config.language) {
+        let mut parts = identifier.split('_').filter(|part| !part.is_empty());
+        let mut camel = parts.next().expect("not only underscores").to_string();
+        for part in parts {
+            camel.push_str(&part[..1].to_ascii_uppercase());
+            camel.push_str(&part[1..]);
+        }
+        if config.capitalize {
+            camel[..1].make_ascii_uppercase();
+        }
+        identifier = camel;
+    } else if config.language == Language::Rust || config.capitalize {
+        identifier.make_ascii_uppercase();
+    }
+
+    if identifier.starts_with(|ch: char| ch.is_ascii_digit()) {
+        identifier.insert(
+            0,
+            if config.language == Language::Nim {
+                'x'
+            } else {
+                '_'
+            },
+        );
+    }
+    let keywords: &[&str] = match config.language {
+        Language::C => &[C_KEYWORDS],
+        Language::Cpp => &[C_KEYWORDS, CPP_KEYWORDS],
+        Language::Python => &[PYTHON_KEYWORDS],
+        Language::Go => &[GO_KEYWORDS],
+        Language::Zig => &[ZIG_KEYWORDS],
+        Language::Nim => &[NIM_KEYWORDS],
+        Language::D => &[D_KEYWORDS],
+        Language::Rust => &[],
+    };
+    // Nim compares names case insensitively, except for the first letter
+    let compared = match config.language {
+        Language::Nim if identifier.starts_with(|ch: char| ch.is_ascii_lowercase()) => {
+            identifier.to_ascii_lowercase()
+        }
+        _ => identifier.clone(),
+    };
+    // Zig integer types of any width, like u7 or i24
+    let zig_integer = config.language == Language::Zig
+        && identifier.len() > 1
+        && identifier.starts_with(['u', 'i'])
+        && identifier[1..].chars().all(|ch| ch.is_ascii_digit());
+    if zig_integer
+        || keywords
+            .iter()
+            .flat_map(|keywords| keywords.split_whitespace())
+            .any(|keyword| keyword == compared)
+    {
+        if config.language == Language::Nim {
+            identifier = format!("`{identifier}`");
+        } else {
+            identifier.push('_');
+        }
+    }
+    Ok(identifier)
+}
+
+/// The name of the struct `--align` wraps a Rust array in, e.g. `AlignedLogoPng` for `LOGO_PNG`.
+fn aligned_struct(var_name: &str) -> String {
+    let mut name = "Aligned".to_string();
+    for part in var_name.split('_') {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.extend(chars.map(|ch| ch.to_ascii_lowercase()));
+        }
+    }
+    name
+}
+
+fn element_type(config: &Config) -> String {
+    match config.element_type {
+        ElementType::Uint8T => format!("uint{}_t", config.word_size * 8),
+        ElementType::UnsignedChar => "unsigned char".to_string(),
+        ElementType::Char => "char".to_string(),
+        ElementType::StdByte => "std::byte".to_string(),
+    }
+}
+
+/// The type of the elements in Go, Zig, Nim and D, which have no `--element-type`.
+fn word_type(config: &Config) -> String {
+    let bits = config.word_size * 8;
+    match (config.language, config.word_size) {
+        (Language::Go, 1) => "byte".to_string(),
+        (Language::Go, _) => format!("uint{bits}"),
+        (Language::Nim, _) => format!("uint{bits}"),
+        (Language::D, 1) => "ubyte".to_string(),
+        (Language::D, 2) => "ushort".to_string(),
+        (Language::D, 4) => "uint".to_string(),
+        (Language::D, _) => "ulong".to_string(),
+        _ => format!("u{bits}"),
+    }
+}
+
+/// The indentation of the elements, a tab as gofmt wants it in Go.
+fn indent(language: Language) -> &'static str {
+    match language {
+        Language::Go => "\t",
+        Language::Zig | Language::D => "    ",
+        _ => "  ",
+    }
+}
+
+/// The type and name of the array, as in its definition and its `extern` declaration.
+fn declaration(config: &Config, var_name: &str, array_size: u64) -> String {
+    let element_type = element_type(config);
+    match config.language {
+        Language::Cpp if config.vector => format!("std::vector<{element_type}> {var_name}"),
+        Language::Cpp => format!("std::array<{element_type}, {array_size}> {var_name}"),
+        _ => format!("{element_type} {var_name}[]"),
+    }
+}
+
+/// Whether the array is declared `const`. Arrays in program memory have to be.
+fn is_const(config: &Config) -> bool {
+    config.const_qualifier || config.progmem
+}
+
+/// What goes before the declaration of the array: the alignment and the storage qualifiers.
+fn specifiers(config: &Config) -> String {
+    let mut specifiers = String::new();
+    if let Some(align) = config.align
+        && config.align_style == AlignStyle::Standard
+    {
+        match config.language {
+            Language::Cpp => specifiers.push_str(&format!("alignas({align}) ")),
+            _ => specifiers.push_str(&format!("_Alignas({align}) ")),
+        }
+    }
+    if config.static_storage {
+        specifiers.push_str("static ");
+    }
+    if config.inline {
+        specifiers.push_str("inline ");
+    }
+    if config.constexpr {
+        specifiers.push_str("constexpr ");
+    }
+    if is_const(config) {
+        specifiers.push_str("const ");
+    }
+    specifiers
+}
+
+/// What goes after the declaration of the array: GCC attributes and `PROGMEM`.
+fn attributes(config: &Config) -> String {
+    let mut attributes = Vec::new();
+    if let Some(align) = config.align
+        && config.align_style == AlignStyle::Gnu
+    {
+        attributes.push(format!("aligned({align})"));
+    }
+    if let Some(section) = &config.section {
+        attributes.push(format!("section(\"{section}\")"));
+    }
+
+    let mut attributes = if attributes.is_empty() {
+        String::new()
+    } else {
+        format!(" __attribute__(({}))", attributes.join(", "))
+    };
+    if config.progmem {
+        attributes.push_str(" PROGMEM");
+    }
+    attributes
+}
+
+/// The headers the declaration needs.
+fn includes(config: &Config) -> String {
+    let mut includes = String::new();
+    if config.language == Language::Cpp {
+        includes.push_str(if config.vector {
+            "#include <vector>\n"
+        } else {
+            "#include <array>\n"
+        });
+    }
+    match (config.element_type, config.language) {
+        (ElementType::Uint8T, Language::Cpp) => includes.push_str("#include <cstdint>\n"),
+        (ElementType::Uint8T, _) => includes.push_str("#include <stdint.h>\n"),
+        (ElementType::StdByte, _) => includes.push_str("#include <cstddef>\n"),
+        _ => {}
+    }
+    if config.progmem {
+        includes.push_str("#include <avr/pgmspace.h>\n");
+    }
+    includes
+}
+
+/// An include guard from the file name of the header, e.g. `BLOB_H` for `fw/blob.h`.
+fn include_guard(header: &Path) -> String {
+    let name = header
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let mut guard: String = name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !guard.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        guard.insert(0, '_');
+    }
+    guard
+}
+
+/// Writes the header declaring the array and its length, included by the generated source.
+fn write_header(
+    header: &Path,
+    var_name: &str,
+    len_name: &str,
+    array_size: u64,
+    config: &Config,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(header).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Could not create header {}: {err}", header.display()),
+        )
+    })?);
+
+    let guard = include_guard(header);
+    if config.pragma_once {
+        writeln!(out, "#pragma once\n")?;
+    } else {
+        writeln!(out, "#ifndef {guard}\n#define {guard}\n")?;
+    }
+    let includes = includes(config);
+    if !includes.is_empty() {
+        writeln!(out, "{includes}")?;
+    }
+    if let Some(namespace) = &config.namespace {
+        writeln!(out, "namespace {namespace} {{\n")?;
+    }
+    let qualifier = if is_const(config) { "const " } else { "" };
+    writeln!(
+        out,
+        "extern {qualifier}{};",
+        declaration(config, var_name, array_size)
+    )?;
+    writeln!(out, "extern const unsigned int {len_name};")?;
+    if let Some(namespace) = &config.namespace {
+        writeln!(out, "\n}}  // namespace {namespace}")?;
+    }
+    if !config.pragma_once {
+        writeln!(out, "\n#endif  // {guard}")?;
+    }
+
+    out.flush()
+}
+
+/// Writes the input as array elements, `elements_per_line` on every line. Elements of more than
+/// one octet are words in the byte order of `--big-endian`, a trailing partial word is padded as
+/// `--padding` says.
+fn write_elements<R: Read, W: Write>(
+    reader: &mut R,
+    out: &mut W,
+    elements_per_line: usize,
+    config: &Config,
+) -> io::Result<()> {
+    let word_size = config.word_size;
+    // Read in chunks of elements_per_line words
+    let mut buffer = vec![0u8; elements_per_line * word_size];
+    let mut total_read: usize = 0;
+    // char literals keep octets above 0x7f from narrowing, std::byte has no conversion from int
+    let (prefix, suffix) = match (config.language, config.element_type) {
+        (Language::C | Language::Cpp, ElementType::Char) => ("'\\x", "'".to_string()),
+        (Language::Cpp, ElementType::StdByte) => ("std::byte{0x", "}".to_string()),
+        // Nim array literals take the type of their elements
+        (Language::Nim, _) => ("0x", format!("'u{}", word_size * 8)),
+        _ => ("0x", String::new()),
+    };
+
+    loop {
+        let to_read: usize = std::cmp::min(buffer.len(), config.length - total_read);
+        let mut bytes_read = dump::read_full(reader, &mut buffer[..to_read])?;
+        if bytes_read == 0 {
+            // EOF reached
+            break;
+        }
+        total_read += bytes_read;
+
+        let partial = bytes_read % word_size;
+        if partial != 0 {
+            let pad = match config.padding {
+                Padding::Zeros => 0x00,
+                Padding::Ones => 0xff,
+                Padding::Error => return Err(partial_word(total_read, config)),
+            };
+            buffer[bytes_read..bytes_read + word_size - partial].fill(pad);
+            bytes_read += word_size - partial;
+        }
+
+        write!(out, "{}", indent(config.language))?;
+        // avoid trailing space for last element
+        for (i, word) in buffer[..bytes_read].chunks(word_size).enumerate() {
+            if i > 0 {
+                write!(out, ", ")?;
+            }
+            if word_size == 1 {
+                write!(
+                    out,
+                    "{prefix}{}{suffix}",
+                    config.format.value(word[0], config.uppercase)
+                )?;
+                continue;
+            }
+
+            let value = bytes::word_value(word, config.little_endian);
+            let digits = 2 * word_size;
+            if config.uppercase {
+                write!(out, "0x{value:0digits$X}{suffix}")?;
+            } else {
+                write!(out, "0x{value:0digits$x}{suffix}")?;
+            }
+        }
+        writeln!(out, ",")?;
+    }
+
+    Ok(())
+}
+
+/// The error for an input that ends within a word with `--padding error`.
+fn partial_word(len: usize, config: &Config) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "{len} octets are no whole number of {}-bit words",
+            config.word_size * 8
+        ),
+    )
+}
+
+/// Writes the octets as the body of a byte string literal, `octets_per_line` on every line. Every
+/// line ends with `\`, which continues the literal on the next line without the line break and
+/// the indentation.
+fn write_byte_string<R: Read, W: Write>(
+    reader: &mut R,
+    out: &mut W,
+    octets_per_line: usize,
+    config: &Config,
+) -> io::Result<()> {
+    let mut buffer = vec![0u8; octets_per_line];
+    let mut total_read: usize = 0;
+    let mut line = String::new();
+
+    loop {
+        let to_read: usize = std::cmp::min(octets_per_line, config.length - total_read);
+        let bytes_read = dump::read_full(reader, &mut buffer[..to_read])?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        line.clear();
+        line.push_str("  ");
+        for (i, &byte) in buffer[..bytes_read].iter().enumerate() {
+            match byte {
+                b'"' => line.push_str("\\\""),
+                b'\\' => line.push_str("\\\\"),
+                // a space at the start of a line would be skipped with the indentation
+                b' ' if i == 0 => line.push_str("\\x20"),
+                b' '..=b'~' => line.push(byte as char),
+                _ => line.push_str(&format!("\\x{byte:02x}")),
+            }
+        }
+        writeln!(out, "{line}\\")?;
+
+        total_read += bytes_read;
+    }
+
+    Ok(())
+}
+
+/// Writes the input as a source code array. With `--header` the array is declared in a header
+/// of its own, which the source includes.
+pub fn generate_array<R: Read, W: Write>(
+    mut reader: R,
+    out: &mut W,
+    config: &Config,
+) -> io::Result<()> {
+    let elements_per_line = if config.cols > 0 {
+        config.cols as usize
+    } else {
+        12
+    };
+
+    // the array is only wrapped in a definition if its size is known
+    let Some(input) = &config.input else {
+        if config.byte_string {
+            writeln!(out, "b\"\\")?;
+            write_byte_string(&mut reader, out, elements_per_line, config)?;
+            writeln!(out, "\"")?;
+        } else {
+            write_elements(&mut reader, out, elements_per_line, config)?;
+        }
+        return Ok(());
+    };
+    // the octets from the seek position, which counts from the end of the file if negative
+    let len = std::cmp::min(
+        fs::metadata(input)?
+            .len()
+            .saturating_sub(config.seek_position()?),
+        config.length as u64,
+    );
+    // the number of elements, a partial word is padded to a whole one
+    if config.padding == Padding::Error && !len.is_multiple_of(config.word_size as u64) {
+        return Err(partial_word(len as usize, config));
+    }
+    let array_size = len.div_ceil(config.word_size as u64);
+
+    let var_name = identifier(&config.var_name, config)?;
+    let len_name = identifier(&format!("{}_len", config.var_name), config)?;
+
+    match config.language {
+        Language::C | Language::Cpp => {
+            match &config.header {
+                Some(header) => {
+                    write_header(header, &var_name, &len_name, array_size, config)?;
+                    let name = header
+                        .file_name()
+                        .map(|name| name.to_string_lossy())
+                        .unwrap_or_default();
+                    writeln!(out, "#include \"{name}\"\n")?;
+                }
+                None => {
+                    let includes = includes(config);
+                    if !includes.is_empty() {
+                        writeln!(out, "{includes}")?;
+                    }
+                }
+            }
+            if let Some(namespace) = &config.namespace {
+                writeln!(out, "namespace {namespace} {{\n")?;
+            }
+            writeln!(
+                out,
+                "{}{}{} = {{",
+                specifiers(config),
+                declaration(config, &var_name, array_size),
+                attributes(config)
+            )?;
+            write_elements(&mut reader, out, elements_per_line, config)?;
+            writeln!(out, "}};")?;
+
+            // like xxd -i, with the storage of the array
+            let mut qualifiers = String::new();
+            if config.static_storage {
+                qualifiers.push_str("static ");
+            }
+            if config.inline {
+                qualifiers.push_str("inline ");
+            }
+            qualifiers.push_str(if config.constexpr {
+                "constexpr"
+            } else {
+                "const"
+            });
+            writeln!(out, "{qualifiers} unsigned int {len_name} = {array_size};")?;
+            if let Some(namespace) = &config.namespace {
+                writeln!(out, "\n}}  // namespace {namespace}")?;
+            }
+        }
+        Language::Rust => {
+            let element = word_type(config);
+            let item = if config.static_storage {
+                "static"
+            } else {
+                "const"
+            };
+            writeln!(out, "pub const {len_name}: usize = {array_size};")?;
+
+            // the type, and what goes before and after the literal
+            let (type_name, before, after) = match config.align {
+                Some(align) => {
+                    let wrapper = aligned_struct(&var_name);
+                    writeln!(out, "\n#[repr(C, align({align}))]")?;
+                    writeln!(out, "pub struct {wrapper}(pub [{element}; {len_name}]);\n")?;
+                    let before = format!("{wrapper}(");
+                    (wrapper, before, ")")
+                }
+                None if config.vector && config.byte_string => {
+                    (format!("&[{element}]"), String::new(), "")
+                }
+                None if config.vector => (format!("&[{element}]"), "&".to_string(), ""),
+                None => (format!("[{element}; {len_name}]"), String::new(), ""),
+            };
+            write!(out, "pub {item} {var_name}: {type_name} = {before}")?;
+            if config.byte_string {
+                // a byte string is a reference to an array, which the array types dereference
+                let deref = if config.vector { "" } else { "*" };
+                writeln!(out, "{deref}b\"\\")?;
+                write_byte_string(&mut reader, out, elements_per_line, config)?;
+                writeln!(out, "\"{after};")?;
+            } else {
+                writeln!(out, "[")?;
+                write_elements(&mut reader, out, elements_per_line, config)?;
+                writeln!(out, "]{after};")?;
+            }
+        }
+        Language::Python => {
+            writeln!(out, "{var_name} = [")?;
+            write_elements(&mut reader, out, elements_per_line, config)?;
+            writeln!(out, "]")?;
+        }
+        // Go has no constant arrays, the slice is a variable
+        Language::Go => {
+            writeln!(out, "const {len_name} = {array_size}\n")?;
+            writeln!(out, "var {var_name} = []{}{{", word_type(config))?;
+            write_elements(&mut reader, out, elements_per_line, config)?;
+            writeln!(out, "}}")?;
+        }
+        Language::Zig => {
+            let element = word_type(config);
+            writeln!(out, "pub const {len_name}: usize = {array_size};\n")?;
+            if config.vector {
+                writeln!(
+                    out,
+                    "pub const {var_name}: []const {element} = &[_]{element}{{"
+                )?;
+            } else {
+                writeln!(out, "pub const {var_name} = [_]{element}{{")?;
+            }
+            write_elements(&mut reader, out, elements_per_line, config)?;
+            writeln!(out, "}};")?;
+        }
+        Language::Nim => {
+            let element = word_type(config);
+            writeln!(out, "const {len_name}* = {array_size}\n")?;
+            if config.vector {
+                writeln!(out, "const {var_name}*: seq[{element}] = @[")?;
+            } else {
+                writeln!(out, "const {var_name}*: array[{len_name}, {element}] = [")?;
+            }
+            write_elements(&mut reader, out, elements_per_line, config)?;
+            writeln!(out, "]")?;
+        }
+        Language::D => {
+            let element = word_type(config);
+            writeln!(out, "enum size_t {len_name} = {array_size};\n")?;
+            if config.vector {
+                writeln!(out, "immutable {element}[] {var_name} = [")?;
+            } else {
+                writeln!(out, "immutable {element}[{len_name}] {var_name} = [")?;
+            }
+            write_elements(&mut reader, out, elements_per_line, config)?;
+            writeln!(out, "];")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,395 @@
+use crate::config::Config;
+use std::io::{self, Read, Write};
+
+/// Octets read at the offset, enough for every type shown.
+const WINDOW: usize = 32;
+/// Characters of text shown at most.
+const TEXT_CHARS: usize = 16;
+
+/// Seconds between 1601-01-01, the FILETIME epoch, and 1970-01-01.
+const FILETIME_EPOCH: i64 = 11_644_473_600;
+
+/// The first `N` octets, if there are that many.
+fn array<const N: usize>(bytes: &[u8]) -> Option<[u8; N]> {
+    bytes.get(..N)?.try_into().ok()
+}
+
+/// Reads the first `N` octets both little-endian and big-endian, `None` if there are fewer.
+fn both<const N: usize, T: ToString>(
+    bytes: &[u8],
+    from_le: impl Fn([u8; N]) -> T,
+    from_be: impl Fn([u8; N]) -> T,
+) -> (Option<String>, Option<String>) {
+    let value = array(bytes);
+    (
+        value.map(|value| from_le(value).to_string()),
+        value.map(|value| from_be(value).to_string()),
+    )
+}
+
+/// The value of a type without a byte order, which leaves the big-endian column empty.
+fn single(value: Option<String>) -> (Option<String>, Option<String>) {
+    (value, Some(String::new()))
+}
+
+/// Converts a half precision float, which Rust has no stable type for, to `f32`.
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let fraction = f32::from(bits & 0x3ff);
+
+    sign * match exponent {
+        0 => fraction * 2f32.powi(-24), // subnormal
+        0x1f if fraction == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => (1.0 + fraction / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Formats a float in scientific notation when it is very large or small, as plain digits would
+/// not fit the table.
+fn float<T: Into<f64> + Copy + std::fmt::Display + std::fmt::LowerExp>(value: T) -> String {
+    let magnitude = value.into().abs();
+    if magnitude == 0.0 || !magnitude.is_finite() || (1e-4..1e16).contains(&magnitude) {
+        value.to_string()
+    } else {
+        format!("{value:e}")
+    }
+}
+
+/// Reads an LEB128 varint: the value and the number of octets it takes.
+fn leb128(bytes: &[u8], signed: bool) -> Option<(i128, usize)> {
+    let mut value: i128 = 0;
+    let mut shift = 0;
+
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        value |= i128::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if signed && byte & 0x40 != 0 {
+                value -= 1 << shift;
+            }
+            return Some((value, i + 1));
+        }
+    }
+
+    None
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time, for the years 1 to 9999.
+fn format_time(seconds: i64) -> String {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+
+    // civil_from_days by Howard Hinnant
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    if !(1..=9999).contains(&year) {
+        return "out of range".to_string();
+    }
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// A FILETIME: 100 ns intervals since 1601.
+fn filetime(ticks: u64) -> String {
+    format_time((ticks / 10_000_000) as i64 - FILETIME_EPOCH)
+}
+
+/// A FAT timestamp: the time in the low and the date in the high 16 bits, little-endian.
+fn dos_time(bytes: [u8; 4]) -> String {
+    let time = u16::from_le_bytes([bytes[0], bytes[1]]);
+    let date = u16::from_le_bytes([bytes[2], bytes[3]]);
+    let (day, month) = (date & 0x1f, (date >> 5) & 0x0f);
+    let (hours, minutes, seconds) = (time >> 11, (time >> 5) & 0x3f, (time & 0x1f) * 2);
+
+    if day == 0 || month == 0 || month > 12 || hours > 23 || minutes > 59 || seconds > 59 {
+        return "invalid".to_string();
+    }
+    format!(
+        "{:04}-{month:02}-{day:02} {hours:02}:{minutes:02}:{seconds:02}",
+        1980 + (date >> 9)
+    )
+}
+
+/// A GUID as Windows stores it, the first three groups little-endian, or in the byte order of
+/// RFC 4122 with `big_endian`.
+fn guid(mut bytes: [u8; 16], big_endian: bool) -> String {
+    if !big_endian {
+        bytes[..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+    }
+    let hex = |range: std::ops::Range<usize>| -> String {
+        bytes[range]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    };
+    format!(
+        "{{{}-{}-{}-{}-{}}}",
+        hex(0..4),
+        hex(4..6),
+        hex(6..8),
+        hex(8..10),
+        hex(10..16)
+    )
+}
+
+/// The text at the start of `chars`, up to the first NUL or undecodable character. Text that
+/// can't be decoded from the first character on is invalid rather than empty.
+fn text(chars: impl Iterator<Item = Option<char>>) -> String {
+    let mut chars = chars.peekable();
+    if chars.peek() == Some(&None) {
+        return "invalid".to_string();
+    }
+    let text: String = chars
+        .map_while(|ch| ch.filter(|&ch| ch != '\0'))
+        .take(TEXT_CHARS)
+        .collect();
+    format!("{text:?}")
+}
+
+fn utf8(bytes: &[u8]) -> String {
+    let (valid, invalid) = match std::str::from_utf8(bytes) {
+        Ok(valid) => (valid, false),
+        // the window may also end within a character
+        Err(err) => (
+            std::str::from_utf8(&bytes[..err.valid_up_to()]).expect("valid up to here"),
+            true,
+        ),
+    };
+    text(valid.chars().map(Some).chain(invalid.then_some(None)))
+}
+
+fn utf16(bytes: &[u8], big_endian: bool) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| {
+        let pair = [pair[0], pair[1]];
+        if big_endian {
+            u16::from_be_bytes(pair)
+        } else {
+            u16::from_le_bytes(pair)
+        }
+    });
+    text(char::decode_utf16(units).map(Result::ok))
+}
+
+/// Interprets the octets at the `--seek` position as every common type and prints a table of
+/// their values, little-endian and big-endian side by side.
+pub fn inspect<R: Read, W: Write>(reader: R, out: &mut W, config: &Config) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(WINDOW);
+    reader.take(WINDOW as u64).read_to_end(&mut bytes)?;
+    if bytes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "No octets at the given offset",
+        ));
+    }
+
+    // values missing for lack of octets show as `-`
+    let mut rows: Vec<(&str, String, String)> = Vec::new();
+    let mut row = |name, (little, big): (Option<String>, Option<String>)| {
+        let missing = || "-".to_string();
+        rows.push((
+            name,
+            little.unwrap_or_else(missing),
+            big.unwrap_or_else(missing),
+        ));
+    };
+
+    row("u8", single(Some(bytes[0].to_string())));
+    row("i8", single(Some((bytes[0] as i8).to_string())));
+    row("u16", both(&bytes, u16::from_le_bytes, u16::from_be_bytes));
+    row("i16", both(&bytes, i16::from_le_bytes, i16::from_be_bytes));
+    row("u32", both(&bytes, u32::from_le_bytes, u32::from_be_bytes));
+    row("i32", both(&bytes, i32::from_le_bytes, i32::from_be_bytes));
+    row("u64", both(&bytes, u64::from_le_bytes, u64::from_be_bytes));
+    row("i64", both(&bytes, i64::from_le_bytes, i64::from_be_bytes));
+    row(
+        "f16",
+        both(
+            &bytes,
+            |half| float(f16_to_f32(u16::from_le_bytes(half))),
+            |half| float(f16_to_f32(u16::from_be_bytes(half))),
+        ),
+    );
+    row(
+        "f32",
+        both(
+            &bytes,
+            |word| float(f32::from_le_bytes(word)),
+            |word| float(f32::from_be_bytes(word)),
+        ),
+    );
+    row(
+        "f64",
+        both(
+            &bytes,
+            |long| float(f64::from_le_bytes(long)),
+            |long| float(f64::from_be_bytes(long)),
+        ),
+    );
+
+    let varint = |signed| {
+        leb128(&bytes, signed).map(|(value, len)| {
+            let unit = if len == 1 { "octet" } else { "octets" };
+            format!("{value} ({len} {unit})")
+        })
+    };
+    row("uleb128", single(varint(false)));
+    row("sleb128", single(varint(true)));
+
+    row(
+        "unix32",
+        both(
+            &bytes,
+            |word| format_time(i64::from(u32::from_le_bytes(word))),
+            |word| format_time(i64::from(u32::from_be_bytes(word))),
+        ),
+    );
+    row(
+        "unix64",
+        both(
+            &bytes,
+            |long| format_time(i64::from_le_bytes(long)),
+            |long| format_time(i64::from_be_bytes(long)),
+        ),
+    );
+    row(
+        "filetime",
+        both(
+            &bytes,
+            |long| filetime(u64::from_le_bytes(long)),
+            |long| filetime(u64::from_be_bytes(long)),
+        ),
+    );
+    row("dos", single(array(&bytes).map(dos_time)));
+    row(
+        "guid",
+        both(&bytes, |id| guid(id, false), |id| guid(id, true)),
+    );
+    row("utf-8", single(Some(utf8(&bytes))));
+    row(
+        "utf-16",
+        (Some(utf16(&bytes, false)), Some(utf16(&bytes, true))),
+    );
+
+    let octets: Vec<String> = bytes
+        .iter()
+        .take(16)
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    writeln!(out, "offset    {:08x}", config.seek_position()?)?;
+    writeln!(out, "octets    {}", octets.join(" "))?;
+    writeln!(out)?;
+
+    let width = rows
+        .iter()
+        .map(|(_, little, _)| little.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("little-endian".len());
+    writeln!(out, "{:<10}{:<width$}  big-endian", "type", "little-endian")?;
+    for (name, little, big) in rows {
+        let line = format!("{name:<10}{little:<width$}  {big}");
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leb128_varints() {
+        assert_eq!(leb128(&[0x7f], false), Some((127, 1)));
+        assert_eq!(leb128(&[0xe5, 0x8e, 0x26, 0xff], false), Some((624_485, 3)));
+        assert_eq!(leb128(&[0x7f], true), Some((-1, 1)));
+        assert_eq!(leb128(&[0xc0, 0xbb, 0x78], true), Some((-123_456, 3)));
+        assert_eq!(leb128(&[0x3f], true), Some((63, 1)));
+        // unterminated, or longer than a 64 bit value takes
+        assert_eq!(leb128(&[0x80, 0x80], false), None);
+        assert_eq!(leb128(&[0x80; 12], false), None);
+    }
+
+    #[test]
+    fn half_precision_floats() {
+        assert_eq!(f16_to_f32(0x3c00), 1.0);
+        assert_eq!(f16_to_f32(0xc000), -2.0);
+        assert_eq!(f16_to_f32(0x7bff), 65504.0);
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7c00), f32::INFINITY);
+        assert_eq!(f16_to_f32(0xfc00), f32::NEG_INFINITY);
+        assert!(f16_to_f32(0x7e00).is_nan());
+        assert_eq!(float(f16_to_f32(0x0001)), "5.9604645e-8");
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(format_time(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_time(951_868_799), "2000-02-29 23:59:59 UTC");
+        assert_eq!(format_time(-1), "1969-12-31 23:59:59 UTC");
+        assert_eq!(format_time(i64::MAX), "out of range");
+
+        assert_eq!(filetime(0), "1601-01-01 00:00:00 UTC");
+        assert_eq!(filetime(116_444_736_000_000_000), "1970-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn dos_times() {
+        // 2020-05-17 13:45:30
+        assert_eq!(dos_time([0xaf, 0x6d, 0xb1, 0x50]), "2020-05-17 13:45:30");
+        assert_eq!(dos_time([0x00, 0x00, 0x21, 0x00]), "1980-01-01 00:00:00");
+        assert_eq!(dos_time([0x00, 0x00, 0x00, 0x00]), "invalid");
+        // month 13
+        assert_eq!(dos_time([0x00, 0x00, 0xa1, 0x01]), "invalid");
+    }
+
+    #[test]
+    fn guids() {
+        let bytes: [u8; 16] = std::array::from_fn(|i| i as u8);
+        assert_eq!(guid(bytes, false), "{03020100-0504-0706-0809-0a0b0c0d0e0f}");
+        assert_eq!(guid(bytes, true), "{00010203-0405-0607-0809-0a0b0c0d0e0f}");
+    }
+
+    #[test]
+    fn utf8_text() {
+        assert_eq!(utf8("héllo\0world".as_bytes()), "\"héllo\"");
+        assert_eq!(utf8(b"a\nb"), "\"a\\nb\"");
+        assert_eq!(utf8(&[b'a'; 20]), format!("\"{}\"", "a".repeat(TEXT_CHARS)));
+        // up to the first undecodable character, also at the end of the window
+        assert_eq!(utf8(b"ab\xffcd"), "\"ab\"");
+        assert_eq!(utf8(b"ab\xc3"), "\"ab\"");
+        assert_eq!(utf8(b"\xffab"), "invalid");
+        assert_eq!(utf8(b"\0ab"), "\"\"");
+    }
+
+    #[test]
+    fn utf16_text() {
+        assert_eq!(utf16(b"h\0i\0\0\0", false), "\"hi\"");
+        assert_eq!(utf16(b"\0h\0i", true), "\"hi\"");
+        // a surrogate pair, and an odd octet at the end
+        assert_eq!(utf16(&[0x3d, 0xd8, 0x00, 0xde, 0x41], false), "\"😀\"");
+        assert_eq!(utf16(&[0x00, 0xd8, 0x41, 0x00], false), "invalid");
+        assert_eq!(utf16(&[0x41, 0x00, 0x00, 0xdc], false), "\"A\"");
+    }
+}
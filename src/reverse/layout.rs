@@ -0,0 +1,277 @@
+use super::{is_digit, parse_octet};
+use crate::config::{Config, DumpLayout};
+use crate::dump;
+
+/// Octets per line of the foreign layouts that have a text part without a delimiter.
+const FOREIGN_COLS: usize = 16;
+
+/// A single line of a dump.
+pub enum DumpLine {
+    /// The offset shown on the line, if any, and its octets.
+    Data { offset: Option<u64>, bytes: Vec<u8> },
+    /// A `*` line: the previous line repeats up to the offset of the next line.
+    Repeat,
+    /// A line holding only an offset, which marks the end of the data.
+    End(u64),
+    /// A line without data, e.g. a packet header.
+    Skip,
+}
+
+/// Guesses the layout from a single line. Returns `None` for lines that fit no layout or that
+/// several layouts share, like empty, `*` or offset only lines.
+pub fn detect(line: &str) -> Option<DumpLayout> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let first = *tokens.first()?;
+
+    if line.starts_with(char::is_whitespace) && first.starts_with("0x") && first.ends_with(':') {
+        return Some(DumpLayout::Tcpdump);
+    }
+    if first.starts_with("0x")
+        && (first.ends_with(':') || tokens.get(1).is_some_and(|token| token.starts_with('<')))
+    {
+        return Some(DumpLayout::Gdb);
+    }
+    if first.ends_with(':') {
+        return Some(DumpLayout::Hexxer);
+    }
+
+    let second = *tokens.get(1)?;
+    if !is_hex(first) || !is_hex(second) {
+        return None;
+    }
+    let gap = line.trim_start()[first.len()..]
+        .find(|ch: char| !ch.is_whitespace())
+        .unwrap_or(0);
+
+    match (first.len(), second.len()) {
+        (8, 2) if gap >= 2 && line.contains('|') => Some(DumpLayout::HexdumpC),
+        (7, 4) => Some(DumpLayout::HexdumpX),
+        (6, 2) => Some(DumpLayout::Od),
+        (4, 2) if gap >= 2 => Some(DumpLayout::Wireshark),
+        _ => None,
+    }
+}
+
+/// Parses one line of a dump in the given layout.
+pub fn parse(line: &str, layout: DumpLayout, config: &Config) -> Result<DumpLine, String> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.trim().is_empty() {
+        return Ok(DumpLine::Skip);
+    }
+    if line.trim() == "*" {
+        return Ok(DumpLine::Repeat);
+    }
+
+    match layout {
+        DumpLayout::Auto | DumpLayout::Hexxer => parse_hexxer(line, config),
+        DumpLayout::HexdumpC => parse_offset_first(line, Some('|'), usize::MAX, false),
+        DumpLayout::HexdumpX => parse_offset_first(line, None, usize::MAX, true),
+        DumpLayout::Od => parse_offset_first(line, Some('>'), usize::MAX, false),
+        DumpLayout::Wireshark => parse_offset_first(line, None, 2, false),
+        DumpLayout::Gdb => parse_gdb(line),
+        DumpLayout::Tcpdump => parse_tcpdump(line),
+    }
+}
+
+fn is_hex(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+fn parse_hex_offset(token: &str) -> Result<u64, String> {
+    let digits = token.strip_prefix("0x").unwrap_or(token);
+    u64::from_str_radix(digits, 16).map_err(|_| format!("invalid offset '{token}'"))
+}
+
+/// Splits the octet part of a line into its groups of hex digits. Reading stops at more than
+/// `max_gap` consecutive blanks, at the first group that is not made of hex digits or after
+/// `FOREIGN_COLS` octets.
+fn hex_groups(data: &str, max_gap: usize) -> Vec<&str> {
+    let mut groups = Vec::new();
+    let mut octets = 0;
+    let mut rest = data.trim_start();
+
+    while octets < FOREIGN_COLS {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let group = &rest[..end];
+        if !is_hex(group) {
+            break;
+        }
+        groups.push(group);
+        octets += group.len() / 2;
+
+        let next = rest[end..].trim_start();
+        if rest.len() - end - next.len() > max_gap {
+            break;
+        }
+        rest = next;
+    }
+
+    groups
+}
+
+/// Turns groups of hex digits into octets. With `little_endian` every group is a word whose
+/// least significant octet comes first.
+pub fn decode_groups(groups: &[&str], little_endian: bool) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+
+    for group in groups {
+        if group.len() % 2 != 0 {
+            return Err(format!("odd number of hex digits in '{group}'"));
+        }
+        let start = bytes.len();
+        for pair in group.as_bytes().chunks(2) {
+            let pair = std::str::from_utf8(pair).expect("digits are ASCII");
+            bytes
+                .push(u8::from_str_radix(pair, 16).map_err(|_| format!("invalid octet '{pair}'"))?);
+        }
+        if little_endian {
+            bytes[start..].reverse();
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Parses one line of the layout `dump` produces:
+/// `<offset>: <octet groups> <text>`, where the offset part is optional. Lines starting with `#`,
+/// like the legend of highlights, are skipped.
+///
+/// The octet part ends after `cols` octets, 32 for `--cols 0` like `dump` writes them, at two
+/// consecutive spaces or at the first character that does not form an octet. `dump` puts a
+/// single space before the text part only, so it is the number of octets that keeps text made
+/// of digits from being read as data. With `--little-endian` the octets of every group are in
+/// reverse order, and a partial word at the end may be right-aligned in its group with leading
+/// spaces, as `xxd -e` writes it.
+fn parse_hexxer(line: &str, config: &Config) -> Result<DumpLine, String> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') {
+        return Ok(DumpLine::Skip);
+    }
+    let (offset, data_part) = match trimmed.split_once(':') {
+        Some((token, rest)) if !token.contains(char::is_whitespace) => {
+            let radix = if config.decimal_offset { 10 } else { 16 };
+            let offset = u64::from_str_radix(token, radix)
+                .map_err(|_| format!("invalid offset '{token}'"))?;
+            (Some(offset), rest)
+        }
+        _ => (None, trimmed),
+    };
+    let data_part = data_part.strip_prefix(' ').unwrap_or(data_part).as_bytes();
+    let width = config.format.width();
+    let cols = dump::octets_per_line(config.cols);
+
+    let is_blank = |ch: &&u8| **ch == b' ' || **ch == b'\t';
+    let token_len = |start: usize| {
+        data_part[start..]
+            .iter()
+            .take_while(|ch| !is_blank(ch))
+            .count()
+    };
+    // the first group, including the padding of a partial word, is as wide as every group
+    let leading = data_part.iter().take_while(is_blank).count();
+    let group_width = leading + token_len(leading);
+    let padded_word = |i: usize, blanks: usize| {
+        let digits = token_len(i + blanks);
+        // the padding follows the space that separates the group from the previous one
+        let padding = if i == 0 { blanks } else { blanks - 1 };
+        digits > 0 && digits.is_multiple_of(width) && padding + digits == group_width
+    };
+
+    let mut bytes = Vec::new();
+    let mut group_start = 0;
+    let mut i = 0;
+    while i < data_part.len() && bytes.len() < cols {
+        if is_blank(&&data_part[i]) {
+            let blanks = data_part[i..].iter().take_while(is_blank).count();
+            if blanks > 1 && !(config.little_endian && padded_word(i, blanks)) {
+                break;
+            }
+            if config.little_endian {
+                bytes[group_start..].reverse();
+            }
+            group_start = bytes.len();
+            i += blanks;
+            continue;
+        }
+
+        let Some(digits) = data_part.get(i..i + width) else {
+            break;
+        };
+        if !digits.iter().all(|&ch| is_digit(ch, config.format)) {
+            break;
+        }
+        bytes.push(parse_octet(digits, config.format)?);
+        i += width;
+    }
+    if config.little_endian {
+        bytes[group_start..].reverse();
+    }
+
+    Ok(DumpLine::Data { offset, bytes })
+}
+
+/// Parses the layouts of `hexdump`, `od` and Wireshark, which start with a bare hex offset
+/// followed by groups of hex digits. The text part, if any, starts at `text_delimiter` or
+/// after more than `max_gap` blanks. A line with only an offset marks the end of the data.
+fn parse_offset_first(
+    line: &str,
+    text_delimiter: Option<char>,
+    max_gap: usize,
+    little_endian: bool,
+) -> Result<DumpLine, String> {
+    let line = match text_delimiter {
+        Some(delimiter) => line.split(delimiter).next().unwrap_or_default(),
+        None => line,
+    };
+    let trimmed = line.trim_start();
+    let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let offset = parse_hex_offset(&trimmed[..end])?;
+
+    let groups = hex_groups(&trimmed[end..], max_gap);
+    if groups.is_empty() {
+        return Ok(DumpLine::End(offset));
+    }
+
+    Ok(DumpLine::Data {
+        offset: Some(offset),
+        bytes: decode_groups(&groups, little_endian)?,
+    })
+}
+
+/// Parses the output of gdb's `x/Nxb`, e.g. `0x401000 <main+4>:\t0x55\t0x48`.
+fn parse_gdb(line: &str) -> Result<DumpLine, String> {
+    let Some((address, data_part)) = line.trim().split_once(':') else {
+        return Ok(DumpLine::Skip);
+    };
+    let address = address.split_whitespace().next().unwrap_or_default();
+
+    let mut bytes = Vec::new();
+    for token in data_part.split_whitespace() {
+        let Some(digits) = token.strip_prefix("0x") else {
+            break;
+        };
+        bytes.push(u8::from_str_radix(digits, 16).map_err(|_| format!("invalid octet '{token}'"))?);
+    }
+
+    Ok(DumpLine::Data {
+        offset: Some(parse_hex_offset(address)?),
+        bytes,
+    })
+}
+
+/// Parses the hex lines of `tcpdump -X`, e.g. `\t0x0010:  4500 0034  E..4`. All other lines,
+/// like the packet headers, are skipped.
+fn parse_tcpdump(line: &str) -> Result<DumpLine, String> {
+    let trimmed = line.trim_start();
+    let Some((offset, data_part)) = trimmed.split_once(':') else {
+        return Ok(DumpLine::Skip);
+    };
+    if !offset.starts_with("0x") || offset.contains(char::is_whitespace) {
+        return Ok(DumpLine::Skip);
+    }
+
+    Ok(DumpLine::Data {
+        offset: Some(parse_hex_offset(offset)?),
+        bytes: decode_groups(&hex_groups(data_part, 1), false)?,
+    })
+}
@@ -0,0 +1,779 @@
+mod layout;
+
+use crate::config::{Config, DumpLayout, Format};
+use layout::DumpLine;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+
+const ESC: char = '\x1b';
+
+/// Number of lines examined to detect the layout of a dump.
+const DETECTION_LINES: usize = 32;
+
+/// Output of `reverse`, which places data at absolute positions.
+trait WriteAt {
+    fn write_at(&mut self, offset: u64, data: &[u8]) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+/// Writes data at absolute positions into a stream that can only move forward.
+/// Gaps between two positions are filled with NUL bytes.
+struct OffsetWriter<W: Write> {
+    inner: W,
+    position: u64,
+}
+
+impl<W: Write> OffsetWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, position: 0 }
+    }
+}
+
+impl<W: Write> WriteAt for OffsetWriter<W> {
+    fn write_at(&mut self, offset: u64, data: &[u8]) -> io::Result<()> {
+        if offset < self.position {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Cannot seek backwards to offset {offset:#x} on non-seekable output (current position {:#x})",
+                    self.position
+                ),
+            ));
+        }
+
+        let zeros = [0u8; 4096];
+        let mut gap = offset - self.position;
+        while gap > 0 {
+            let len = std::cmp::min(gap, zeros.len() as u64) as usize;
+            self.inner.write_all(&zeros[..len])?;
+            gap -= len as u64;
+        }
+
+        self.inner.write_all(data)?;
+        self.position = offset + data.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Overwrites data at absolute positions of a seekable target and leaves everything else as is.
+struct PatchWriter<W: Write + Seek> {
+    inner: W,
+    position: u64,
+}
+
+impl<W: Write + Seek> PatchWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, position: 0 }
+    }
+}
+
+impl<W: Write + Seek> WriteAt for PatchWriter<W> {
+    fn write_at(&mut self, offset: u64, data: &[u8]) -> io::Result<()> {
+        if offset != self.position {
+            self.inner.seek(SeekFrom::Start(offset))?;
+        }
+        self.inner.write_all(data)?;
+        self.position = offset + data.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Removes the ANSI escape sequences `colorize` emits, so colored dumps can be reversed.
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != ESC {
+            stripped.push(ch);
+            continue;
+        }
+        // CSI sequences end with a byte in the range @ to ~
+        if chars.next() == Some('[') {
+            for ch in chars.by_ref() {
+                if ('@'..='~').contains(&ch) {
+                    break;
+                }
+            }
+        }
+    }
+
+    stripped
+}
+
+pub(super) fn is_digit(ch: u8, format: Format) -> bool {
+    (ch as char).is_digit(format.radix())
+}
+
+/// Parses the digits of one octet, which must all be valid digits of `format`.
+pub(super) fn parse_octet(digits: &[u8], format: Format) -> Result<u8, String> {
+    let digits = std::str::from_utf8(digits).expect("digits are ASCII");
+    format
+        .parse(digits)
+        .ok_or_else(|| format!("octet '{digits}' out of range"))
+}
+
+/// Detects the layout of a dump from its first lines. The lines read for that are put in front
+/// of the returned reader again. Dumps no line of which is conclusive are taken for `dump` output.
+fn detect_layout<R: BufRead>(mut reader: R) -> io::Result<(impl BufRead, DumpLayout)> {
+    let mut head = Vec::new();
+    let mut layout = None;
+
+    for _ in 0..DETECTION_LINES {
+        let start = head.len();
+        if reader.read_until(b'\n', &mut head)? == 0 {
+            break;
+        }
+        layout = layout::detect(&strip_ansi(&String::from_utf8_lossy(&head[start..])));
+        if layout.is_some() {
+            break;
+        }
+    }
+
+    Ok((
+        io::Cursor::new(head).chain(reader),
+        layout.unwrap_or(DumpLayout::Hexxer),
+    ))
+}
+
+/// Places the octets of a dump at their offsets, shifted by `--seek`, and expands `*` lines.
+///
+/// The latest data line is held back until the next line is known, because an end offset may
+/// cut off padding in the last line, as `hexdump -x` adds for files of odd length.
+struct Reconstructor<'a> {
+    out: &'a mut dyn WriteAt,
+    seek: i64,
+    remaining: usize,
+    pending: Option<(u64, Vec<u8>)>,
+    previous: Vec<u8>,
+    repeating: bool,
+    next_offset: u64,
+}
+
+impl<'a> Reconstructor<'a> {
+    fn new(out: &'a mut dyn WriteAt, config: &Config) -> Self {
+        Self {
+            out,
+            seek: config.seek,
+            remaining: config.length,
+            pending: None,
+            previous: Vec::new(),
+            repeating: false,
+            next_offset: 0,
+        }
+    }
+
+    fn write(&mut self, offset: u64, bytes: &[u8]) -> io::Result<()> {
+        let target = offset.checked_add_signed(self.seek).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Seeking by {} moves offset {offset:#x} out of range",
+                    self.seek
+                ),
+            )
+        })?;
+
+        let bytes = &bytes[..std::cmp::min(bytes.len(), self.remaining)];
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.out.write_at(target, bytes)?;
+        self.remaining -= bytes.len();
+        Ok(())
+    }
+
+    fn flush_pending(&mut self) -> io::Result<()> {
+        match self.pending.take() {
+            Some((offset, bytes)) => self.write(offset, &bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// Repeats the previous line from where it ended up to `until`.
+    fn fill(&mut self, until: u64) -> io::Result<()> {
+        self.repeating = false;
+        if self.previous.is_empty() {
+            return Ok(());
+        }
+
+        let previous = std::mem::take(&mut self.previous);
+        while self.next_offset < until && self.remaining > 0 {
+            let len = std::cmp::min(previous.len() as u64, until - self.next_offset) as usize;
+            self.write(self.next_offset, &previous[..len])?;
+            self.next_offset += len as u64;
+        }
+        self.previous = previous;
+        Ok(())
+    }
+
+    fn data(&mut self, offset: u64, bytes: Vec<u8>) -> io::Result<()> {
+        if self.repeating {
+            self.flush_pending()?;
+            self.fill(offset)?;
+        }
+        self.flush_pending()?;
+
+        self.next_offset = offset + bytes.len() as u64;
+        self.previous.clone_from(&bytes);
+        self.pending = Some((offset, bytes));
+        Ok(())
+    }
+
+    fn repeat(&mut self) -> io::Result<()> {
+        self.flush_pending()?;
+        self.repeating = true;
+        Ok(())
+    }
+
+    fn end(&mut self, offset: u64) -> io::Result<()> {
+        if let Some((start, bytes)) = &mut self.pending {
+            bytes.truncate(offset.saturating_sub(*start) as usize);
+        }
+        self.flush_pending()?;
+        if self.repeating {
+            self.fill(offset)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.flush_pending()
+    }
+}
+
+/// Reads a dump of one of the supported layouts and places every line's octets at the offset
+/// shown on that line, shifted by `--seek`. Lines without an offset continue where the previous
+/// line ended.
+///
+/// gdb shows memory addresses, which are taken relative to the first address. Packet dumps start
+/// every packet at offset 0, so packets are placed one after another.
+fn reverse_dump<R: BufRead>(reader: R, out: &mut dyn WriteAt, config: &Config) -> io::Result<()> {
+    let (mut reader, layout): (Box<dyn BufRead>, _) = match config.layout {
+        DumpLayout::Auto => {
+            let (reader, layout) = detect_layout(reader)?;
+            (Box::new(reader), layout)
+        }
+        layout => (Box::new(reader), layout),
+    };
+
+    let mut reconstructor = Reconstructor::new(out, config);
+    let mut raw = Vec::new();
+    let mut line_number = 0;
+    let mut origin: Option<u64> = None;
+    let mut packet_start: u64 = 0;
+
+    while reconstructor.remaining > 0 {
+        raw.clear();
+        if reader.read_until(b'\n', &mut raw)? == 0 {
+            // EOF reached
+            break;
+        }
+        line_number += 1;
+
+        let line = strip_ansi(&String::from_utf8_lossy(&raw));
+        let with_line_number = |msg: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {line_number}: {msg}"),
+            )
+        };
+        let parsed = layout::parse(&line, layout, config).map_err(with_line_number)?;
+
+        match parsed {
+            DumpLine::Data { offset, bytes } => {
+                let offset = match (offset, layout) {
+                    (None, _) => reconstructor.next_offset,
+                    (Some(address), DumpLayout::Gdb) => {
+                        let origin = *origin.get_or_insert(address);
+                        address.checked_sub(origin).ok_or_else(|| {
+                            with_line_number(format!(
+                                "address {address:#x} lies before the first address {origin:#x}"
+                            ))
+                        })?
+                    }
+                    (Some(0), DumpLayout::Wireshark | DumpLayout::Tcpdump) => {
+                        packet_start = reconstructor.next_offset;
+                        packet_start
+                    }
+                    (Some(offset), DumpLayout::Wireshark | DumpLayout::Tcpdump) => {
+                        packet_start + offset
+                    }
+                    (Some(offset), _) => offset,
+                };
+                reconstructor.data(offset, bytes)?;
+            }
+            DumpLine::Repeat => reconstructor.repeat()?,
+            DumpLine::End(offset) => reconstructor.end(offset)?,
+            DumpLine::Skip => {}
+        }
+    }
+
+    reconstructor.finish()
+}
+
+/// Position of the first octet for input without offsets, where `--seek` is the start position.
+fn start_position(config: &Config) -> io::Result<u64> {
+    if config.seek < 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Seeking to negative offsets is only supported for dumps with offsets",
+        ));
+    }
+    Ok(config.seek.unsigned_abs())
+}
+
+/// Reads a continuous stream of octets as `dump --plain` produces it. Whitespace and line breaks
+/// are ignored anywhere in the stream, so lines may have any length.
+fn reverse_plain<R: BufRead>(
+    mut reader: R,
+    out: &mut dyn WriteAt,
+    config: &Config,
+) -> io::Result<()> {
+    let mut position = start_position(config)?;
+    let mut remaining = config.length;
+    let mut line_number = 1;
+    let width = config.format.width();
+    let mut digits = Vec::with_capacity(width);
+    let mut bytes = Vec::new();
+
+    while remaining > 0 {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            // EOF reached
+            break;
+        }
+
+        bytes.clear();
+        let mut consumed = 0;
+        for &ch in chunk {
+            if bytes.len() == remaining {
+                break;
+            }
+            consumed += 1;
+
+            if ch.is_ascii_whitespace() {
+                if ch == b'\n' {
+                    line_number += 1;
+                }
+                continue;
+            }
+
+            if !is_digit(ch, config.format) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {line_number}: invalid digit '{}'", ch.escape_ascii()),
+                ));
+            }
+            digits.push(ch);
+            if digits.len() == width {
+                let octet = parse_octet(&digits, config.format).map_err(|msg| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {line_number}: {msg}"),
+                    )
+                })?;
+                bytes.push(octet);
+                digits.clear();
+            }
+        }
+        reader.consume(consumed);
+
+        out.write_at(position, &bytes)?;
+        position += bytes.len() as u64;
+        remaining -= bytes.len();
+    }
+
+    if remaining > 0 && !digits.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Incomplete octet at the end of the plain hexdump",
+        ));
+    }
+
+    Ok(())
+}
+
+/// The length of the string or character literal at the start of `source` up to its closing
+/// quote, both quotes included. `None` if it does not end.
+fn quoted_len(source: &str) -> Option<usize> {
+    let quote = source.chars().next()?;
+    let mut escaped = false;
+    for (pos, ch) in source.char_indices().skip(1) {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if ch == quote => return Some(pos + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The end of the string or character literal starting at `pos`, the end of the source if it
+/// does not end. `None` if no literal starts there. A `'` right after a letter or digit starts
+/// the type suffix of a Nim literal, like in `0xff'u8`.
+fn quoted_end(source: &str, pos: usize) -> Option<usize> {
+    let suffix = source[..pos].ends_with(|ch: char| ch.is_ascii_alphanumeric());
+    if !source[pos..].starts_with(['"', '\'']) || (suffix && source[pos..].starts_with('\'')) {
+        return None;
+    }
+    Some(pos + quoted_len(&source[pos..]).unwrap_or(source.len() - pos))
+}
+
+/// Removes `//`, `/* */` and `#` comments. String and character literals are kept as they are,
+/// the octets of a byte string or a `'#'` may look like a comment. Newlines are kept so line
+/// numbers stay intact.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((pos, ch)) = chars.next() {
+        if let Some(end) = quoted_end(source, pos) {
+            stripped.push_str(&source[pos..end]);
+            while chars.next_if(|&(next, _)| next < end).is_some() {}
+            continue;
+        }
+        match (ch, chars.peek().map(|&(_, ch)| ch)) {
+            ('/', Some('/')) | ('#', _) => {
+                // skip to the end of the line, but keep the newline itself
+                while chars.next_if(|&(_, ch)| ch != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for (_, ch) in chars.by_ref() {
+                    if ch == '\n' {
+                        stripped.push(ch);
+                    }
+                    if previous == '*' && ch == '/' {
+                        break;
+                    }
+                    previous = ch;
+                }
+                // keep tokens on both sides of the comment apart
+                stripped.push(' ');
+            }
+            _ => stripped.push(ch),
+        }
+    }
+
+    stripped
+}
+
+/// Where the array starts in the value of an assignment: its opening bracket or the `b` of a byte
+/// string. What may come before it is skipped, like the `&` and `*` of references, the `@` of a
+/// Nim seq, the wrapper struct of an aligned Rust array and the type of a Go or Zig array literal.
+/// `None` if the value is no array, like a length.
+fn array_start(value: &str) -> Option<usize> {
+    // the value may start on the next line
+    let mut pos = value.len() - value.trim_start().len();
+    while let Some(ch) = value[pos..].chars().next() {
+        match ch {
+            '[' => {
+                // a type like []byte or [_]u8 is followed by the name of the elements
+                let close = pos + value[pos..].find(']')?;
+                let is_type = value[pos + 1..close]
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+                    && value[close + 1..].starts_with(|ch: char| ch.is_ascii_alphabetic());
+                if !is_type {
+                    return Some(pos);
+                }
+                pos = close;
+            }
+            '{' => return Some(pos),
+            'b' if value[pos + 1..].starts_with('"') => return Some(pos),
+            '&' | '*' | '@' | '(' | ':' | '_' | ' ' | '\t' => {}
+            ch if ch.is_ascii_alphanumeric() => {}
+            _ => return None,
+        }
+        pos += ch.len_utf8();
+    }
+    None
+}
+
+/// The length of the array at the start of `source`, up to the bracket matching the opening one
+/// or to the end of the byte string. `None` if the array does not end.
+fn array_len(source: &str) -> Option<usize> {
+    if let Some(string) = source.strip_prefix('b') {
+        return Some(1 + quoted_len(string)?);
+    }
+
+    let mut depth = 0;
+    let mut chars = source.char_indices().peekable();
+    while let Some((pos, ch)) = chars.next() {
+        if let Some(end) = quoted_end(source, pos) {
+            while chars.next_if(|&(next, _)| next < end).is_some() {}
+            continue;
+        }
+        match ch {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(pos + 1);
+        }
+    }
+    None
+}
+
+/// The positions of the `=` of assignments, outside of string and character literals.
+fn assignments(source: &str) -> Vec<usize> {
+    let mut assignments = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((pos, ch)) = chars.next() {
+        if let Some(end) = quoted_end(source, pos) {
+            while chars.next_if(|&(next, _)| next < end).is_some() {}
+        } else if ch == '=' {
+            assignments.push(pos);
+        }
+    }
+    assignments
+}
+
+/// Locates the initializer of the first assignment of an array, from its opening bracket or byte
+/// string to its end. Assignments of other values, like the length constant, are skipped.
+/// Without an assignment the whole source is taken as the initializer. Returns the initializer
+/// and the line it starts on.
+fn find_initializer(source: &str) -> Result<(&str, usize), String> {
+    let assignments = assignments(source);
+    if assignments.is_empty() {
+        return Ok((source, 1));
+    }
+    let start = assignments
+        .iter()
+        .find_map(|&assignment| Some(assignment + 1 + array_start(&source[assignment + 1..])?))
+        .ok_or_else(|| "no array initializer found after '='".to_string())?;
+    let len =
+        array_len(&source[start..]).ok_or_else(|| "unterminated array initializer".to_string())?;
+
+    let line = source[..start].matches('\n').count() + 1;
+    Ok((&source[start..start + len], line))
+}
+
+/// Parses an integer literal as C, C++, Rust, Python, Go, Zig, Nim or D write it, e.g. `0xff`,
+/// `255`, `0b1`, `0o17`, `0xffu8`, `0xff'u8` or `1_000`.
+fn parse_literal(literal: &str) -> Option<u64> {
+    let literal = match literal.split_once('\'') {
+        Some((literal, suffix))
+            if suffix.starts_with(['u', 'i'])
+                && suffix[1..].chars().all(|ch| ch.is_ascii_digit()) =>
+        {
+            literal
+        }
+        Some(_) => return None,
+        None => literal,
+    };
+    let literal = literal.replace('_', "");
+    let lower = literal.to_ascii_lowercase();
+    let (radix, digits) = if let Some(digits) = lower.strip_prefix("0x") {
+        (16, digits)
+    } else if let Some(digits) = lower.strip_prefix("0b") {
+        (2, digits)
+    } else if let Some(digits) = lower.strip_prefix("0o") {
+        (8, digits)
+    } else {
+        (10, lower.as_str())
+    };
+
+    let digits = ["u8", "i8", "u"]
+        .iter()
+        .find_map(|suffix| digits.strip_suffix(suffix))
+        .unwrap_or(digits);
+    u64::from_str_radix(digits, radix).ok()
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+/// The octet of an escape sequence like `\x7f`, `\n` or `\\`, after the backslash.
+fn unescape(escape: char, chars: &mut Chars) -> Result<u8, String> {
+    Ok(match escape {
+        'x' => {
+            let digits: String = (0..2)
+                .filter_map(|_| chars.next())
+                .map(|(_, ch)| ch)
+                .collect();
+            u8::from_str_radix(&digits, 16).map_err(|_| format!("invalid escape '\\x{digits}'"))?
+        }
+        'n' => b'\n',
+        'r' => b'\r',
+        't' => b'\t',
+        '0' => b'\0',
+        '\\' | '\'' | '"' => escape as u8,
+        _ => return Err(format!("invalid escape '\\{escape}'")),
+    })
+}
+
+/// Reads the octets of a byte string literal after its opening `b"`, up to the closing `"`. A `\`
+/// at the end of a line continues the literal without the line break and the indentation.
+fn byte_string(
+    chars: &mut Chars,
+    bytes: &mut Vec<u8>,
+    line_number: &mut usize,
+) -> Result<(), String> {
+    while let Some((_, ch)) = chars.next() {
+        let byte = match ch {
+            '"' => return Ok(()),
+            '\\' => {
+                let Some((_, escape)) = chars.next() else {
+                    break;
+                };
+                if escape == '\r' || escape == '\n' {
+                    if escape == '\n' {
+                        *line_number += 1;
+                    }
+                    while let Some((_, ch)) = chars.next_if(|&(_, ch)| ch.is_whitespace()) {
+                        if ch == '\n' {
+                            *line_number += 1;
+                        }
+                    }
+                    continue;
+                }
+                unescape(escape, chars).map_err(|msg| format!("line {line_number}: {msg}"))?
+            }
+            '\n' => {
+                *line_number += 1;
+                b'\n'
+            }
+            ch if ch.is_ascii() => ch as u8,
+            _ => {
+                return Err(format!(
+                    "line {line_number}: '{ch}' is no ASCII character of a byte string"
+                ));
+            }
+        };
+        bytes.push(byte);
+    }
+
+    Err(format!("line {line_number}: unterminated byte string"))
+}
+
+/// Reads the octets of an initializer: integer, character and byte string literals, separated by
+/// commas, brackets and whitespace. `std::byte` elements are read as the literal in their braces.
+fn parse_initializer(initializer: &str, first_line: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut line_number = first_line;
+    let mut chars = initializer.char_indices().peekable();
+    let is_literal = |&(_, ch): &(usize, char)| ch.is_ascii_alphanumeric() || "_:'".contains(ch);
+
+    while let Some((start, ch)) = chars.next() {
+        match ch {
+            '\n' => line_number += 1,
+            'b' if chars.next_if(|&(_, ch)| ch == '"').is_some() => {
+                byte_string(&mut chars, &mut bytes, &mut line_number)?;
+            }
+            ch if ch.is_ascii_alphanumeric() => {
+                let mut end = start + 1;
+                while let Some((pos, _)) = chars.next_if(is_literal) {
+                    end = pos + 1;
+                }
+                let literal = &initializer[start..end];
+                if literal == "std::byte" {
+                    continue;
+                }
+                let value = parse_literal(literal)
+                    .ok_or_else(|| format!("line {line_number}: invalid literal '{literal}'"))?;
+                let octet = u8::try_from(value).map_err(|_| {
+                    format!("line {line_number}: value '{literal}' does not fit into an octet")
+                })?;
+                bytes.push(octet);
+            }
+            '\'' => {
+                let octet = match chars.next() {
+                    Some((_, '\\')) => {
+                        let (_, escape) = chars.next().unwrap_or((start, ' '));
+                        unescape(escape, &mut chars)
+                            .map_err(|msg| format!("line {line_number}: {msg}"))?
+                    }
+                    Some((_, ch)) if ch.is_ascii() && ch != '\'' => ch as u8,
+                    _ => return Err(format!("line {line_number}: invalid character literal")),
+                };
+                if chars.next_if(|&(_, ch)| ch == '\'').is_none() {
+                    return Err(format!(
+                        "line {line_number}: unterminated character literal"
+                    ));
+                }
+                bytes.push(octet);
+            }
+            ch if ch.is_whitespace() || ",{}[]()&*@".contains(ch) => {}
+            _ => return Err(format!("line {line_number}: unexpected '{ch}'")),
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Reads the initializer of a source code array as `generate` produces it, a list of literals or
+/// a Rust byte string. Hand-edited files may mix hex, decimal, binary and octal literals and
+/// contain comments.
+fn reverse_array<R: Read>(mut reader: R, out: &mut dyn WriteAt, config: &Config) -> io::Result<()> {
+    let mut raw = Vec::new();
+    reader.read_to_end(&mut raw)?;
+    let source = strip_comments(&String::from_utf8_lossy(&raw));
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let (initializer, first_line) = find_initializer(&source).map_err(invalid)?;
+    let mut bytes = parse_initializer(initializer, first_line).map_err(invalid)?;
+    if bytes.is_empty() {
+        return Err(invalid(format!(
+            "line {first_line}: no octets found in the array initializer"
+        )));
+    }
+
+    bytes.truncate(config.length);
+    out.write_at(start_position(config)?, &bytes)
+}
+
+/// Opens the output of `reverse`. Files are seekable, so offsets may appear in any order, except
+/// when appending, where positions are relative to the end of the file.
+fn get_writer(config: &Config) -> io::Result<Box<dyn WriteAt>> {
+    if let Some(path) = &config.patch {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        return Ok(Box::new(PatchWriter::new(io::BufWriter::new(file))));
+    }
+
+    match &config.output {
+        Some(path) if config.append => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            Ok(Box::new(OffsetWriter::new(io::BufWriter::new(file))))
+        }
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(path)?;
+            Ok(Box::new(PatchWriter::new(io::BufWriter::new(file))))
+        }
+        None => Ok(Box::new(OffsetWriter::new(io::BufWriter::new(
+            io::stdout().lock(),
+        )))),
+    }
+}
+
+/// Converts a hexdump or a generated source code array back into binary and writes it to the
+/// output or, with `--patch`, into an existing file in place.
+pub fn reverse<R: Read>(reader: R, config: &Config) -> io::Result<()> {
+    let reader = BufReader::new(reader);
+    let mut out = get_writer(config)?;
+
+    if config.array {
+        reverse_array(reader, out.as_mut(), config)?;
+    } else if config.plain {
+        reverse_plain(reader, out.as_mut(), config)?;
+    } else {
+        reverse_dump(reader, out.as_mut(), config)?;
+    }
+
+    out.flush()
+}
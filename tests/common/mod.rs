@@ -0,0 +1,89 @@
+//! Runs the `hexx` binary in a directory of its own, with an empty configuration file unless a
+//! test writes one.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+pub struct Sandbox {
+    dir: PathBuf,
+}
+
+impl Sandbox {
+    /// Creates an empty directory named after the test.
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("hexxer-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("Could not create the test directory");
+        let sandbox = Self { dir };
+        sandbox.config("");
+        sandbox
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn write(&self, name: &str, contents: &[u8]) -> PathBuf {
+        let path = self.path(name);
+        fs::write(&path, contents).expect("Could not write a test file");
+        path
+    }
+
+    pub fn read(&self, name: &str) -> Vec<u8> {
+        fs::read(self.path(name)).expect("Could not read a test file")
+    }
+
+    /// Replaces the configuration file every run reads.
+    pub fn config(&self, contents: &str) {
+        self.write("config.toml", contents.as_bytes());
+    }
+
+    /// Runs hexx with `stdin` as input.
+    pub fn run(&self, args: &[&str], stdin: &[u8]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_hexx"))
+            .args(args)
+            .current_dir(&self.dir)
+            .env("HEXXER_CONFIG", self.path("config.toml"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Could not run hexx");
+        // hexx may fail before reading all of its input
+        let _ = child.stdin.take().expect("stdin is piped").write_all(stdin);
+        child.wait_with_output().expect("Could not run hexx")
+    }
+
+    /// Runs hexx, which has to succeed, and returns its output.
+    pub fn hexx(&self, args: &[&str], stdin: &[u8]) -> Vec<u8> {
+        let output = self.run(args, stdin);
+        assert!(
+            output.status.success(),
+            "hexx {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output.stdout
+    }
+
+    /// Runs hexx, which has to fail, and returns its error message.
+    pub fn fails(&self, args: &[&str], stdin: &[u8]) -> String {
+        let output = self.run(args, stdin);
+        assert!(
+            !output.status.success(),
+            "hexx {} succeeded unexpectedly",
+            args.join(" ")
+        );
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
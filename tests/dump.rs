@@ -0,0 +1,143 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn color_table_to_output_file() {
+    let sandbox = Sandbox::new("dump-color-table");
+
+    let stdout = sandbox.hexx(&["dump", "--print-color-table", "-O", "table.txt"], b"");
+    assert!(stdout.is_empty());
+    let table = String::from_utf8(sandbox.read("table.txt")).expect("table is text");
+    assert!(table.starts_with("dark\n"));
+    // no colors in files
+    assert!(!table.contains('\x1b'));
+}
+
+#[test]
+fn little_endian_partial_word_is_right_aligned() {
+    let sandbox = Sandbox::new("dump-little-endian");
+    sandbox.write("data.bin", b"abcdefghijklmnopqrs");
+
+    let dump = sandbox.hexx(&["dump", "-e", "data.bin"], b"");
+    assert_eq!(
+        String::from_utf8_lossy(&dump),
+        "00000000: 64636261 68676665 6c6b6a69 706f6e6d abcdefghijklmnop\n\
+         00000010:   737271                            qrs\n"
+    );
+}
+
+#[test]
+fn little_endian_group_must_be_a_power_of_two() {
+    let sandbox = Sandbox::new("dump-little-endian-group");
+    sandbox.write("data.bin", b"abcdefghijklmnop");
+
+    let message = sandbox.fails(&["dump", "-e", "-g", "3", "data.bin"], b"");
+    assert!(message.contains("must be a power of 2 with -e"));
+    sandbox.hexx(&["dump", "-e", "-g", "8", "data.bin"], b"");
+}
+
+#[test]
+fn plain_cols_from_the_configuration_file() {
+    let sandbox = Sandbox::new("dump-plain-cols");
+    sandbox.config("[dump]\ncols = 8\n");
+    sandbox.write("data.bin", &[0xab; 40]);
+
+    let dump = sandbox.hexx(&["dump", "-p", "data.bin"], b"");
+    let lines: Vec<&[u8]> = dump.split(|&ch| ch == b'\n').collect();
+    assert_eq!(lines[0].len(), 60);
+
+    sandbox.config("[dump]\ncols = 8\nplain-cols = 4\n");
+    let dump = sandbox.hexx(&["dump", "-p", "data.bin"], b"");
+    assert!(dump.starts_with(b"abababab\n"));
+}
+
+#[test]
+fn uppercase_from_the_configuration_file_can_be_turned_off() {
+    let sandbox = Sandbox::new("dump-no-uppercase");
+    sandbox.config("[dump]\nuppercase = true\n\n[generate]\nuppercase = true\n");
+    sandbox.write("data.bin", &[0xab, 0xcd]);
+
+    let dump = sandbox.hexx(&["dump", "data.bin"], b"");
+    assert!(dump.starts_with(b"00000000: ABCD"));
+    let dump = sandbox.hexx(&["dump", "--no-uppercase", "data.bin"], b"");
+    assert!(dump.starts_with(b"00000000: abcd"));
+    // the last of the two flags wins
+    let dump = sandbox.hexx(&["dump", "--no-uppercase", "-u", "data.bin"], b"");
+    assert!(dump.starts_with(b"00000000: ABCD"));
+
+    let c = sandbox.hexx(&["generate", "--no-uppercase", "data.bin"], b"");
+    assert!(String::from_utf8_lossy(&c).contains("0xab, 0xcd"));
+}
+
+#[test]
+fn partial_value_is_padded_with_zeros() {
+    let sandbox = Sandbox::new("dump-partial-value");
+    sandbox.write("data.bin", &[1, 2, 3, 4, 5, 6]);
+
+    let dump = sandbox.hexx(
+        &["dump", "-c", "4", "-t", "u32le", "-t", "u32be", "data.bin"],
+        b"",
+    );
+    let dump = String::from_utf8_lossy(&dump);
+    let values: Vec<&str> = dump
+        .lines()
+        .filter(|line| line.trim_start().starts_with("u32"))
+        .map(|line| line.split_whitespace().last().unwrap_or_default())
+        .collect();
+    assert_eq!(values, ["67305985", "16909060", "1541", "84279296"]);
+}
+
+#[test]
+fn highlight_legend() {
+    let sandbox = Sandbox::new("dump-highlight-legend");
+    sandbox.write("data.bin", b"xxxabxxxxxxxxxab");
+
+    let dump = sandbox.hexx(
+        &[
+            "dump",
+            "-c",
+            "4",
+            "--color",
+            "never",
+            "--highlight",
+            "0..3",
+            "--highlight-pattern",
+            "ab",
+            "--highlight-pattern",
+            "0x7a",
+            "data.bin",
+        ],
+        b"",
+    );
+    let dump = String::from_utf8_lossy(&dump);
+    let legend: Vec<&str> = dump.lines().filter(|line| line.starts_with('#')).collect();
+    assert_eq!(
+        legend,
+        [
+            "# 0..3  3 bytes",
+            "# ab  2 matches at 00000003, 0000000e",
+            "# 0x7a  no matches",
+        ]
+    );
+}
+
+#[test]
+fn u32_over_a_short_final_line() {
+    let sandbox = Sandbox::new("dump-short-final-line");
+    sandbox.write("data.bin", &[1, 2, 3, 4, 5, 6]);
+
+    let dump = sandbox.hexx(
+        &[
+            "dump", "-c", "4", "-t", "u32", "--color", "never", "data.bin",
+        ],
+        b"",
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&dump),
+        "00000000: 01 02 03 04 \u{2401}\u{2402}\u{2403}\u{2404}\n   \
+         u32le     67305985\n\
+         00000004: 05 06       \u{2405}\u{2406}\n   \
+         u32le         1541\n"
+    );
+}
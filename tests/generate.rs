@@ -0,0 +1,232 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn negative_seek() {
+    let sandbox = Sandbox::new("generate-negative-seek");
+    let data: Vec<u8> = (0..24).collect();
+    sandbox.write("data.bin", &data);
+
+    let c = sandbox.hexx(&["generate", "-s", "-4", "data.bin"], b"");
+    let c = String::from_utf8_lossy(&c);
+    assert!(c.contains("0x14, 0x15, 0x16, 0x17,"));
+    assert!(c.contains("data_bin_len = 4;"));
+
+    let rust = sandbox.hexx(&["generate", "-L", "rust", "-s", "-4", "data.bin"], b"");
+    assert!(String::from_utf8_lossy(&rust).contains("DATA_BIN_LEN: usize = 4;"));
+}
+
+#[test]
+fn header_and_source_pair() {
+    let sandbox = Sandbox::new("generate-header");
+    sandbox.write("my-blob.bin", b"abcdefghij");
+
+    let source = sandbox.hexx(
+        &[
+            "generate",
+            "-s",
+            "-4",
+            "--header",
+            "my-blob.h",
+            "my-blob.bin",
+        ],
+        b"",
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&source),
+        "#include \"my-blob.h\"\n\
+         \n\
+         uint8_t my_blob_bin[] = {\n  \
+         0x67, 0x68, 0x69, 0x6a,\n\
+         };\n\
+         const unsigned int my_blob_bin_len = 4;\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&sandbox.read("my-blob.h")),
+        "#ifndef MY_BLOB_H\n\
+         #define MY_BLOB_H\n\
+         \n\
+         #include <stdint.h>\n\
+         \n\
+         extern uint8_t my_blob_bin[];\n\
+         extern const unsigned int my_blob_bin_len;\n\
+         \n\
+         #endif  // MY_BLOB_H\n"
+    );
+}
+
+#[test]
+fn include_guard_names() {
+    let sandbox = Sandbox::new("generate-include-guard");
+    sandbox.write("data.bin", b"abcdefghij");
+
+    // a guard can't start with a digit
+    sandbox.hexx(
+        &[
+            "generate",
+            "-L",
+            "cpp",
+            "-s",
+            "-4",
+            "--namespace",
+            "assets",
+            "--header",
+            "2nd.blob.hpp",
+            "data.bin",
+        ],
+        b"",
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&sandbox.read("2nd.blob.hpp")),
+        "#ifndef _2ND_BLOB_HPP\n\
+         #define _2ND_BLOB_HPP\n\
+         \n\
+         #include <array>\n\
+         #include <cstdint>\n\
+         \n\
+         namespace assets {\n\
+         \n\
+         extern std::array<uint8_t, 4> data_bin;\n\
+         extern const unsigned int data_bin_len;\n\
+         \n\
+         }  // namespace assets\n\
+         \n\
+         #endif  // _2ND_BLOB_HPP\n"
+    );
+
+    sandbox.hexx(
+        &[
+            "generate",
+            "--pragma-once",
+            "--header",
+            "once.h",
+            "data.bin",
+        ],
+        b"",
+    );
+    let header = sandbox.read("once.h");
+    assert!(header.starts_with(b"#pragma once\n\n#include <stdint.h>\n"));
+    assert!(!String::from_utf8_lossy(&header).contains("#ifndef"));
+}
+
+/// Octets that look like the syntax around them: quotes, escapes, comments and brackets.
+fn tricky_data() -> Vec<u8> {
+    let mut data = b" a /* b // c # d = [e] \"f\" \\ g \n  x".to_vec();
+    data.extend(0..=u8::MAX);
+    data
+}
+
+/// Generates source code with `options`, reverses it and compares the result with the input.
+fn assert_round_trip(sandbox: &Sandbox, options: &[&str]) {
+    let data = tricky_data();
+    sandbox.write("data.bin", &data);
+
+    let mut args = vec!["generate"];
+    args.extend_from_slice(options);
+    args.push("data.bin");
+    let source = sandbox.hexx(&args, b"");
+    assert_eq!(
+        sandbox.hexx(&["reverse", "-a"], &source),
+        data,
+        "round trip of generate {}",
+        options.join(" ")
+    );
+}
+
+#[test]
+fn rust_round_trip() {
+    let sandbox = Sandbox::new("generate-rust");
+    for storage in [&[][..], &["--static"]] {
+        for shape in [&[][..], &["-v"], &["--align", "16"]] {
+            for literal in [&[][..], &["--byte-string"]] {
+                let mut options = vec!["-L", "rust"];
+                options.extend_from_slice(storage);
+                options.extend_from_slice(shape);
+                options.extend_from_slice(literal);
+                assert_round_trip(&sandbox, &options);
+            }
+        }
+    }
+
+    // stdin gives a bare byte string
+    let data = tricky_data();
+    let source = sandbox.hexx(&["generate", "-L", "rust", "--byte-string"], &data);
+    assert_eq!(sandbox.hexx(&["reverse", "-a"], &source), data);
+}
+
+#[test]
+fn element_type_round_trip() {
+    let sandbox = Sandbox::new("generate-element-type");
+    for language in ["c", "cpp"] {
+        for element_type in ["uint8_t", "unsigned-char", "char"] {
+            assert_round_trip(&sandbox, &["-L", language, "--element-type", element_type]);
+        }
+    }
+    assert_round_trip(&sandbox, &["-L", "cpp", "--element-type", "std::byte"]);
+    assert_round_trip(
+        &sandbox,
+        &["-L", "cpp", "--element-type", "std::byte", "-v"],
+    );
+}
+
+#[test]
+fn go_zig_nim_d_round_trip() {
+    let sandbox = Sandbox::new("generate-languages");
+    for language in ["python", "go", "zig", "nim", "d"] {
+        assert_round_trip(&sandbox, &["-L", language]);
+        assert_round_trip(&sandbox, &["-L", language, "-v"]);
+    }
+    // a keyword in backticks
+    assert_round_trip(&sandbox, &["-L", "nim", "-n", "type"]);
+}
+
+#[test]
+fn word_packing_and_padding() {
+    let sandbox = Sandbox::new("generate-words");
+    sandbox.write("w.bin", b"abcde");
+    let generate = |options: &[&str]| {
+        let mut args = vec!["generate"];
+        args.extend_from_slice(options);
+        args.push("w.bin");
+        String::from_utf8(sandbox.hexx(&args, b"")).unwrap()
+    };
+
+    let zeros = generate(&["--word-size", "32"]);
+    assert!(zeros.contains("uint32_t w_bin[] = {\n  0x64636261, 0x00000065,\n};"));
+    assert!(zeros.contains("w_bin_len = 2;"));
+    let ones = generate(&["--word-size", "32", "--padding", "ones"]);
+    assert!(ones.contains("0x64636261, 0xffffff65,"));
+
+    let big_endian = generate(&[
+        "--word-size",
+        "16",
+        "--big-endian",
+        "--padding",
+        "ones",
+        "-u",
+    ]);
+    assert!(big_endian.contains("uint16_t w_bin[] = {\n  0x6162, 0x6364, 0x65FF,\n};"));
+    assert!(big_endian.contains("w_bin_len = 3;"));
+
+    let rust = generate(&["-L", "rust", "--word-size", "16"]);
+    assert!(rust.contains("pub const W_BIN: [u16; W_BIN_LEN] = [\n  0x6261, 0x6463, 0x0065,\n];"));
+
+    assert_eq!(
+        sandbox.fails(
+            &[
+                "generate",
+                "--word-size",
+                "32",
+                "--padding",
+                "error",
+                "w.bin"
+            ],
+            b""
+        ),
+        "Error: 5 octets are no whole number of 32-bit words\n"
+    );
+    // whole words need no padding
+    sandbox.write("w.bin", b"abcd");
+    assert!(generate(&["--word-size", "32", "--padding", "error"]).contains("0x64636261,\n"));
+}
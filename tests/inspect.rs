@@ -0,0 +1,26 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn values_missing_for_lack_of_octets() {
+    let sandbox = Sandbox::new("inspect-short");
+    sandbox.write("data.bin", &[0xff, 0x01]);
+
+    let table = sandbox.hexx(&["inspect", "data.bin"], b"");
+    let table = String::from_utf8_lossy(&table);
+    let row = |name: &str| {
+        table
+            .lines()
+            .find(|line| line.split_whitespace().next() == Some(name))
+            .map(|line| line.split_whitespace().skip(1).collect::<Vec<_>>())
+            .expect("row is shown")
+    };
+    assert_eq!(row("u16"), ["511", "65281"]);
+    assert_eq!(row("u32"), ["-", "-"]);
+    assert_eq!(row("guid"), ["-", "-"]);
+    // no byte order, no big-endian column
+    assert_eq!(row("u8"), ["255"]);
+    assert_eq!(row("dos"), ["-"]);
+    assert_eq!(row("utf-8"), ["invalid"]);
+}
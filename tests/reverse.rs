@@ -0,0 +1,51 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn long_lines_with_digits_in_the_text() {
+    let sandbox = Sandbox::new("reverse-long-lines");
+    let data = b"deadbeef".repeat(8);
+    sandbox.write("data.bin", &data);
+
+    let dump = sandbox.hexx(&["dump", "-c", "0", "data.bin"], b"");
+    assert_eq!(sandbox.hexx(&["reverse", "-c", "0"], &dump), data);
+}
+
+#[test]
+fn little_endian_partial_word() {
+    let sandbox = Sandbox::new("reverse-little-endian");
+    let data: Vec<u8> = (0..=100).collect();
+    sandbox.write("data.bin", &data);
+
+    for grouping in ["2", "4", "8"] {
+        let dump = sandbox.hexx(&["dump", "-e", "-g", grouping, "data.bin"], b"");
+        assert_eq!(sandbox.hexx(&["reverse", "-e"], &dump), data);
+    }
+
+    // as xxd -e writes it
+    let xxd = b"00000000: 64636261 68676665 6c6b6a69 706f6e6d  abcdefghijklmnop\n\
+                00000010:   737271                             qrs\n";
+    assert_eq!(
+        sandbox.hexx(&["reverse", "-e"], xxd),
+        b"abcdefghijklmnopqrs"
+    );
+}
+
+#[test]
+fn defaults_from_the_configuration_file() {
+    let sandbox = Sandbox::new("reverse-config");
+    sandbox.config("[dump]\nformat = \"binary\"\ncols = 8\n");
+    let data: Vec<u8> = (0..=100).rev().collect();
+    sandbox.write("data.bin", &data);
+
+    let dump = sandbox.hexx(&["dump", "data.bin"], b"");
+    assert_eq!(sandbox.hexx(&["reverse"], &dump), data);
+}
+
+#[test]
+fn empty_initializer_is_an_error() {
+    let sandbox = Sandbox::new("reverse-empty-array");
+    let message = sandbox.fails(&["reverse", "-a"], b"var data = []byte{\n}\n");
+    assert!(message.contains("no octets"), "{message}");
+}